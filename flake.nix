@@ -29,13 +29,15 @@
             pkgs.libxkbcommon
             pkgs.libGL
           ];
-          nativeBuildInputs = [ pkgs.patchelf ];
+          nativeBuildInputs = [ pkgs.patchelf pkgs.makeWrapper ];
           CARGO_INCREMENTAL = 0;
           src = ./.;
           doCheck = false;
           cargoLock.lockFile = ./Cargo.lock;
           postFixup = ''
             patchelf --add-rpath "${runtime_dep}" "$out/bin/${name}"
+            # the file chooser of the image tool
+            wrapProgram "$out/bin/${name}" --prefix PATH : "${pkgs.lib.makeBinPath [ pkgs.zenity ]}"
           '';
         };
 
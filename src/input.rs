@@ -0,0 +1,415 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, channel};
+
+use anyhow::{Context, Result, anyhow, bail};
+use eframe::egui::{Color32, ColorImage};
+use strum::{EnumString, IntoStaticStr};
+
+use crate::ui::shape::picture;
+
+/// The byte order of raw pixels.
+#[derive(Clone, Copy, Debug, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum RawFormat {
+    Rgba,
+    /// rgb with an ignored padding byte
+    Rgbx,
+    Bgra,
+    /// bgr with an ignored padding byte, `xrgb8888` on little endian machines
+    Bgrx,
+    Rgb,
+    Bgr,
+    Gray,
+}
+
+impl RawFormat {
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            RawFormat::Rgba | RawFormat::Rgbx | RawFormat::Bgra | RawFormat::Bgrx => 4,
+            RawFormat::Rgb | RawFormat::Bgr => 3,
+            RawFormat::Gray => 1,
+        }
+    }
+}
+
+/// The size and format of raw pixel input, written as `WxH:format`, e.g. `1920x1080:rgba`.
+#[derive(Clone, Copy, Debug)]
+pub struct RawInput {
+    pub width: usize,
+    pub height: usize,
+    pub format: RawFormat,
+}
+
+impl FromStr for RawInput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (size, format) = s
+            .split_once(':')
+            .ok_or("expected WxH:format, e.g. 1920x1080:rgba")?;
+        let (width, height) = size
+            .split_once('x')
+            .ok_or("expected the size as WxH, e.g. 1920x1080")?;
+        let format = RawFormat::from_str(format).map_err(|_| {
+            format!("unknown format {format:?}, expected rgba, rgbx, bgra, bgrx, rgb, bgr or gray")
+        })?;
+        Ok(RawInput {
+            width: width
+                .parse()
+                .map_err(|_| format!("invalid width {width:?}"))?,
+            height: height
+                .parse()
+                .map_err(|_| format!("invalid height {height:?}"))?,
+            format,
+        })
+    }
+}
+
+/// A decoded screenshot.
+pub struct Screenshot {
+    pub image: ColorImage,
+    /// Whether no pixel is transparent
+    pub opaque: bool,
+}
+
+/// Decode the screenshot on a background thread, so that the window shows up in the meantime.
+pub fn decode_in_background(bytes: Vec<u8>, raw: Option<RawInput>) -> Receiver<Result<Screenshot>> {
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        let screenshot = decode(&bytes, raw).map(|image| Screenshot {
+            opaque: image.pixels.iter().all(|p| p.is_opaque()),
+            image,
+        });
+        let _ = sender.send(screenshot);
+    });
+    receiver
+}
+
+/// Decode raw pixels, PPM, PGM and PAM directly, and everything else with the image crate.
+pub fn decode(bytes: &[u8], raw: Option<RawInput>) -> Result<ColorImage> {
+    if let Some(raw) = raw {
+        return decode_raw(bytes, raw);
+    }
+    match bytes.get(..2) {
+        Some(b"P5" | b"P6") => decode_pnm(bytes),
+        Some(b"P7") => decode_pam(bytes),
+        _ => picture::load_from_memory(bytes).map_err(|e| anyhow!("{e}")),
+    }
+}
+
+fn decode_raw(bytes: &[u8], raw: RawInput) -> Result<ColorImage> {
+    let RawInput {
+        width,
+        height,
+        format,
+    } = raw;
+    let len = sample_count(width, height, format.bytes_per_pixel())?;
+    if bytes.len() < len {
+        bail!(
+            "{width}x{height} {} pixels need {len} bytes, got {}",
+            <&'static str>::from(format),
+            bytes.len()
+        );
+    }
+    let data = &bytes[..len];
+    let pixels = match format {
+        RawFormat::Rgba => to_pixels(data, 4, |p| {
+            Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3])
+        }),
+        RawFormat::Rgbx => to_pixels(data, 4, |p| Color32::from_rgb(p[0], p[1], p[2])),
+        RawFormat::Bgra => to_pixels(data, 4, |p| {
+            Color32::from_rgba_unmultiplied(p[2], p[1], p[0], p[3])
+        }),
+        RawFormat::Bgrx => to_pixels(data, 4, |p| Color32::from_rgb(p[2], p[1], p[0])),
+        RawFormat::Rgb => to_pixels(data, 3, |p| Color32::from_rgb(p[0], p[1], p[2])),
+        RawFormat::Bgr => to_pixels(data, 3, |p| Color32::from_rgb(p[2], p[1], p[0])),
+        RawFormat::Gray => to_pixels(data, 1, |p| Color32::from_gray(p[0])),
+    };
+    Ok(ColorImage::new([width, height], pixels))
+}
+
+/// Binary PGM (`P5`) and PPM (`P6`), as written by `grim -t ppm`.
+fn decode_pnm(bytes: &[u8]) -> Result<ColorImage> {
+    let mut header = Header::new(bytes);
+    let channels = match header.token()? {
+        b"P5" => 1,
+        _ => 3,
+    };
+    let width = header.number()?;
+    let height = header.number()?;
+    let max_value = header.number()?;
+    // a single whitespace separates the header from the samples
+    let data = &bytes[header.pos + 1..];
+    let samples = samples(data, sample_count(width, height, channels)?, max_value)?;
+    Ok(ColorImage::new(
+        [width, height],
+        channels_to_pixels(&samples, channels),
+    ))
+}
+
+/// PAM (`P7`) with the GRAYSCALE, GRAYSCALE_ALPHA, RGB or RGB_ALPHA tuple types.
+fn decode_pam(bytes: &[u8]) -> Result<ColorImage> {
+    let mut header = Header::new(bytes);
+    header.token()?;
+    let (mut width, mut height, mut depth, mut max_value) = (None, None, None, None);
+    loop {
+        match header.token()? {
+            b"WIDTH" => width = Some(header.number()?),
+            b"HEIGHT" => height = Some(header.number()?),
+            b"DEPTH" => depth = Some(header.number()?),
+            b"MAXVAL" => max_value = Some(header.number()?),
+            b"TUPLTYPE" => {
+                header.token()?;
+            }
+            b"ENDHDR" => break,
+            token => bail!(
+                "unknown PAM header field {:?}",
+                String::from_utf8_lossy(token)
+            ),
+        }
+    }
+    let (Some(width), Some(height), Some(channels), Some(max_value)) =
+        (width, height, depth, max_value)
+    else {
+        bail!("PAM header misses WIDTH, HEIGHT, DEPTH or MAXVAL");
+    };
+    if !(1..=4).contains(&channels) {
+        bail!("PAM images with a depth of {channels} are not supported");
+    }
+    let data = &bytes[header.pos + 1..];
+    let samples = samples(data, sample_count(width, height, channels)?, max_value)?;
+    Ok(ColorImage::new(
+        [width, height],
+        channels_to_pixels(&samples, channels),
+    ))
+}
+
+/// The number of samples of an image, which must not overflow for absurd sizes.
+fn sample_count(width: usize, height: usize, channels: usize) -> Result<usize> {
+    width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .with_context(|| format!("the image size {width}x{height} is too large"))
+}
+
+/// `count` samples scaled to 8 bits, 16 bit samples are big endian.
+fn samples(data: &[u8], count: usize, max_value: usize) -> Result<Cow<'_, [u8]>> {
+    if !(1..=u16::MAX as usize).contains(&max_value) {
+        bail!("invalid maximum sample value {max_value}");
+    }
+    let sample_size = if max_value > u8::MAX as usize { 2 } else { 1 };
+    let data = count
+        .checked_mul(sample_size)
+        .and_then(|len| data.get(..len))
+        .context("the image data is truncated")?;
+    Ok(match (sample_size, max_value) {
+        (1, 255) => Cow::Borrowed(data),
+        (1, _) => Cow::Owned(
+            data.iter()
+                .map(|v| (*v as usize * 255 / max_value) as u8)
+                .collect(),
+        ),
+        _ => Cow::Owned(
+            data.chunks_exact(2)
+                .map(|v| (u16::from_be_bytes([v[0], v[1]]) as usize * 255 / max_value) as u8)
+                .collect(),
+        ),
+    })
+}
+
+fn channels_to_pixels(samples: &[u8], channels: usize) -> Vec<Color32> {
+    match channels {
+        1 => to_pixels(samples, 1, |p| Color32::from_gray(p[0])),
+        2 => to_pixels(samples, 2, |p| {
+            Color32::from_rgba_unmultiplied(p[0], p[0], p[0], p[1])
+        }),
+        3 => to_pixels(samples, 3, |p| Color32::from_rgb(p[0], p[1], p[2])),
+        _ => to_pixels(samples, 4, |p| {
+            Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3])
+        }),
+    }
+}
+
+#[inline]
+fn to_pixels(data: &[u8], bytes_per_pixel: usize, f: impl Fn(&[u8]) -> Color32) -> Vec<Color32> {
+    data.chunks_exact(bytes_per_pixel).map(f).collect()
+}
+
+/// The whitespace separated tokens of a PNM or PAM header, comments are skipped.
+struct Header<'a> {
+    bytes: &'a [u8],
+    /// the position after the last token
+    pos: usize,
+}
+
+impl<'a> Header<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn token(&mut self) -> Result<&'a [u8]> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'#') => {
+                    while self.bytes.get(self.pos).is_some_and(|b| *b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => bail!("the image header is truncated"),
+            }
+        }
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        if self.pos >= self.bytes.len() {
+            bail!("the image header is truncated");
+        }
+        Ok(&self.bytes[start..self.pos])
+    }
+
+    fn number(&mut self) -> Result<usize> {
+        let token = self.token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .with_context(|| {
+                format!(
+                    "invalid number {:?} in the image header",
+                    String::from_utf8_lossy(token)
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_raw_input() {
+        let raw: RawInput = "1920x1080:bgrx".parse().unwrap();
+        assert_eq!((raw.width, raw.height), (1920, 1080));
+        assert!(matches!(raw.format, RawFormat::Bgrx));
+
+        assert!("1920x1080".parse::<RawInput>().is_err());
+        assert!("1920:rgba".parse::<RawInput>().is_err());
+        assert!("1920xabc:rgba".parse::<RawInput>().is_err());
+        assert!("1920x1080:argb".parse::<RawInput>().is_err());
+    }
+
+    #[test]
+    fn decode_raw_pixels() {
+        let raw = "2x1:bgra".parse().unwrap();
+        let image = decode(&[3, 2, 1, 255, 6, 5, 4, 255], Some(raw)).unwrap();
+        assert_eq!(image.size, [2, 1]);
+        assert_eq!(
+            image.pixels,
+            [Color32::from_rgb(1, 2, 3), Color32::from_rgb(4, 5, 6)]
+        );
+
+        let raw = "1x1:rgbx".parse().unwrap();
+        let image = decode(&[1, 2, 3, 0], Some(raw)).unwrap();
+        assert_eq!(image.pixels, [Color32::from_rgb(1, 2, 3)]);
+
+        let raw = "2x2:gray".parse().unwrap();
+        assert!(decode(&[0, 0, 0], Some(raw)).is_err());
+    }
+
+    #[test]
+    fn reject_overflowing_sizes() {
+        let raw = "99999999999x99999999999:rgba".parse().unwrap();
+        assert!(decode(&[0; 16], Some(raw)).is_err());
+        assert!(decode(b"P6 99999999999 99999999999 255\n\0\0\0", None).is_err());
+        assert!(
+            decode(
+                b"P7\nWIDTH 99999999999\nHEIGHT 99999999999\nDEPTH 4\nMAXVAL 255\nENDHDR\n\0",
+                None
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn decode_ppm_with_comments() {
+        let image = decode(
+            b"P6\n# made by grim\n2 1\n# max\n255\n\x01\x02\x03\x04\x05\x06",
+            None,
+        )
+        .unwrap();
+        assert_eq!(image.size, [2, 1]);
+        assert_eq!(
+            image.pixels,
+            [Color32::from_rgb(1, 2, 3), Color32::from_rgb(4, 5, 6)]
+        );
+    }
+
+    #[test]
+    fn scale_samples() {
+        // a maximum value of 15 is scaled to 255
+        let image = decode(b"P5 2 1 15\n\x0f\x05", None).unwrap();
+        assert_eq!(
+            image.pixels,
+            [Color32::from_gray(255), Color32::from_gray(85)]
+        );
+
+        // 16 bit samples are big endian
+        let image = decode(b"P5 2 1 65535\n\xff\xff\x80\x00", None).unwrap();
+        assert_eq!(
+            image.pixels,
+            [Color32::from_gray(255), Color32::from_gray(127)]
+        );
+
+        assert!(decode(b"P5 1 1 0\n\x00", None).is_err());
+        assert!(decode(b"P5 1 1 65536\n\x00\x00", None).is_err());
+    }
+
+    #[test]
+    fn decode_pam() {
+        let image = decode(
+            b"P7\nWIDTH 1\nHEIGHT 2\nDEPTH 2\nMAXVAL 255\nTUPLTYPE GRAYSCALE_ALPHA\nENDHDR\n\x10\xff\x20\x00",
+            None,
+        )
+        .unwrap();
+        assert_eq!(image.size, [1, 2]);
+        assert_eq!(
+            image.pixels,
+            [
+                Color32::from_rgba_unmultiplied(0x10, 0x10, 0x10, 0xff),
+                Color32::TRANSPARENT
+            ]
+        );
+
+        let image = decode(
+            b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n\x01\x02\x03\xff",
+            None,
+        )
+        .unwrap();
+        assert_eq!(image.pixels, [Color32::from_rgb(1, 2, 3)]);
+
+        assert!(decode(b"P7\nWIDTH 1\nHEIGHT 1\nMAXVAL 255\nENDHDR\n\x00", None).is_err());
+        assert!(
+            decode(
+                b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 5\nMAXVAL 255\nENDHDR\n\x00\x00\x00\x00\x00",
+                None
+            )
+            .is_err()
+        );
+        assert!(decode(b"P7\nWIDTH 1\nCOLORS 3\nENDHDR\n", None).is_err());
+    }
+
+    #[test]
+    fn reject_truncated_input() {
+        assert!(decode(b"P6\n2 1\n255\n\x01\x02\x03", None).is_err());
+        assert!(decode(b"P6\n2 1", None).is_err());
+        assert!(decode(b"P6\n# only a comment", None).is_err());
+        assert!(decode(b"P6 2 x 255\n", None).is_err());
+    }
+}
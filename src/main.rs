@@ -1,5 +1,10 @@
 use std::{collections::HashMap, io::Read, sync::Arc};
 
+use crate::input::RawInput;
+use crate::ui::color;
+use crate::ui::crop::{AspectRatio, CropGeometry};
+use crate::ui::shape::stamp;
+use crate::ui::tiled::TiledTexture;
 use crate::ui::window::{edit_window::EditWindow, pin_window::PinWindow};
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -11,6 +16,7 @@ use eframe::{
 };
 use log::warn;
 
+mod input;
 mod ui;
 
 #[derive(Parser)]
@@ -19,6 +25,20 @@ pub struct Arg {
     #[arg(short, long, default_value = "-")]
     pub input: String,
 
+    /// Read the input as raw pixels of the given size and format, e.g. '1920x1080:rgba'.
+    /// Formats: rgba, rgbx, bgra, bgrx, rgb, bgr, gray.
+    #[arg(long, value_name = "WxH:FORMAT")]
+    pub raw: Option<RawInput>,
+
+    /// Start with this range cropped, in image pixels, e.g. '100,200 640x480' as printed by slurp
+    /// or '640x480+100+200' as printed by slop.
+    #[arg(long, value_name = "X,Y WxH")]
+    pub crop: Option<CropGeometry>,
+
+    /// Lock the crop to a width to height ratio, e.g. '16:9'.
+    #[arg(long, value_name = "W:H")]
+    pub crop_ratio: Option<AspectRatio>,
+
     /// Output file, '-' means stdout.
     #[arg(short, long, default_value = "-")]
     pub output: String,
@@ -56,6 +76,8 @@ fn main() -> Result<()> {
     } else {
         std::fs::read(&arg.input).with_context(|| "Failed to read image data from stdin")?
     };
+    // decode while the fonts are loaded and the window is created
+    let screenshot = input::decode_in_background(image_data, arg.raw);
     let fonts_data = load_font(&arg.fonts)?;
     let mut pinned_image: Option<ColorImage> = None;
     let create_with_context = |ctx: &CreationContext| -> Result<Box<dyn eframe::App>, _> {
@@ -75,15 +97,18 @@ fn main() -> Result<()> {
 
         ctx.egui_ctx.set_fonts(fonts);
         egui_extras::install_image_loaders(&ctx.egui_ctx);
+        stamp::load_emoji_font_in_background(&ctx.egui_ctx);
         Ok(Box::new(EditWindow::new(
-            image_data,
+            screenshot,
             font_family,
             arg,
             &mut pinned_image,
         )))
     };
-    eframe::run_native("Slappy", options, Box::new(create_with_context))
-        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let result = eframe::run_native("Slappy", options, Box::new(create_with_context));
+    // the palette is written once instead of on the UI thread whenever a color is used
+    color::save_palette();
+    result.map_err(|e| anyhow::anyhow!("{}", e))?;
 
     if let Some(pinned_image) = pinned_image {
         let window_size = Vec2 {
@@ -104,9 +129,10 @@ fn main() -> Result<()> {
             ..Default::default()
         };
         let create_with_context = |ctx: &CreationContext| -> Result<Box<dyn eframe::App>, _> {
-            Ok(Box::new(PinWindow::new(ctx.egui_ctx.load_texture(
+            Ok(Box::new(PinWindow::new(TiledTexture::new(
+                &ctx.egui_ctx,
                 "pinned_screenshot",
-                pinned_image,
+                &pinned_image,
                 TextureOptions::LINEAR,
             ))))
         };
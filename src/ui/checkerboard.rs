@@ -0,0 +1,30 @@
+use eframe::egui::{
+    Color32, ColorImage, Context, Painter, Pos2, Rect, TextureHandle, TextureOptions,
+};
+
+/// The size of one square on the screen.
+const SQUARE_SIZE: f32 = 8f32;
+const LIGHT: Color32 = Color32::from_gray(0xcc);
+const DARK: Color32 = Color32::from_gray(0x99);
+
+/// The checkerboard shown behind transparent areas.
+pub struct Checkerboard {
+    /// two by two squares, repeated across the painted rect
+    texture: TextureHandle,
+}
+
+impl Checkerboard {
+    pub fn new(ctx: &Context) -> Self {
+        let texture = ctx.load_texture(
+            "checkerboard",
+            ColorImage::new([2, 2], vec![LIGHT, DARK, DARK, LIGHT]),
+            TextureOptions::NEAREST_REPEAT,
+        );
+        Self { texture }
+    }
+
+    pub fn paint(&self, painter: &Painter, rect: Rect) {
+        let uv = Rect::from_min_size(Pos2::ZERO, rect.size() / (2f32 * SQUARE_SIZE));
+        painter.image(self.texture.id(), rect, uv, Color32::WHITE);
+    }
+}
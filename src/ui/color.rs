@@ -0,0 +1,339 @@
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use eframe::egui::{
+    Button, Checkbox, Color32, Context, Id, Label, Response, Rgba, Sense, Stroke, StrokeKind, Ui,
+    Vec2, Widget,
+    color_picker::{Alpha, color_edit_button_rgba},
+    mutex::Mutex,
+};
+use log::warn;
+
+/// The number of recently used colors kept in the palette.
+const RECENT_COLORS: usize = 10;
+
+#[derive(Clone, Copy, Default)]
+struct PickerState {
+    /// The eyedropper is active.
+    active: bool,
+    /// The picker which receives sampled colors and colors from the palette. It is the last used
+    /// one unless the eyedropper is started by its button.
+    target: Option<Id>,
+    /// The pass in which the target picker was shown the last time.
+    target_pass: u64,
+    /// The current color of the target picker.
+    target_color: Color32,
+    /// A color which is not yet taken by its picker.
+    picked: Option<(Id, Rgba)>,
+    /// A color being edited in a picker, it is remembered once the pointer is released.
+    edited: Option<Color32>,
+    /// Copy the hex value of the sampled color to the clipboard.
+    copy: bool,
+}
+
+fn state_id() -> Id {
+    Id::new("color_picker")
+}
+
+fn load_state(ctx: &Context) -> PickerState {
+    ctx.data(|d| d.get_temp(state_id()).unwrap_or_default())
+}
+
+fn store_state(ctx: &Context, state: PickerState) {
+    ctx.data_mut(|d| d.insert_temp(state_id(), state));
+}
+
+/// The favorite and recently used colors, saved in the config directory when the editor is
+/// closed.
+struct Palette {
+    favorites: Vec<Color32>,
+    recent: Vec<Color32>,
+    /// The palette changed since it was loaded.
+    dirty: bool,
+}
+
+static PALETTE: LazyLock<Mutex<Palette>> = LazyLock::new(|| Mutex::new(Palette::load()));
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            favorites: vec![
+                Color32::from_rgb(0xe5, 0x39, 0x35),
+                Color32::from_rgb(0xfb, 0x8c, 0x00),
+                Color32::from_rgb(0xfd, 0xd8, 0x35),
+                Color32::from_rgb(0x43, 0xa0, 0x47),
+                Color32::from_rgb(0x1e, 0x88, 0xe5),
+                Color32::from_rgb(0x8e, 0x24, 0xaa),
+                Color32::BLACK,
+                Color32::WHITE,
+            ],
+            recent: Vec::new(),
+            dirty: false,
+        }
+    }
+}
+
+impl Palette {
+    fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("slappy").join("palette"))
+    }
+
+    /// Read the palette file, which has a line of hex colors for each of `favorites` and `recent`.
+    fn load() -> Self {
+        let Some(content) = Self::path().and_then(|path| std::fs::read_to_string(path).ok()) else {
+            return Default::default();
+        };
+        let mut palette = Self {
+            favorites: Vec::new(),
+            recent: Vec::new(),
+            dirty: false,
+        };
+        for line in content.lines() {
+            let mut words = line.split_whitespace();
+            let colors = match words.next() {
+                Some("favorites") => &mut palette.favorites,
+                Some("recent") => &mut palette.recent,
+                _ => continue,
+            };
+            colors.extend(words.filter_map(|word| Color32::from_hex(word).ok()));
+        }
+        palette.recent.truncate(RECENT_COLORS);
+        palette
+    }
+
+    fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+        let Some(path) = Self::path() else {
+            return;
+        };
+        let line = |name: &str, colors: &[Color32]| {
+            let colors: Vec<String> = colors.iter().map(|c| hex(*c)).collect();
+            format!("{name} {}\n", colors.join(" "))
+        };
+        let content = line("favorites", &self.favorites) + &line("recent", &self.recent);
+        if let Err(e) = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, content))
+        {
+            warn!("Failed to save the palette to {path:?}. Reason: {e}");
+        }
+    }
+
+    fn remember(&mut self, color: Color32) {
+        if self.recent.first() == Some(&color) {
+            return;
+        }
+        self.recent.retain(|c| *c != color);
+        self.recent.insert(0, color);
+        self.recent.truncate(RECENT_COLORS);
+        self.dirty = true;
+    }
+}
+
+/// Write the palette if it changed, this blocks and is called once the editor is closed.
+pub fn save_palette() {
+    PALETTE.lock().save();
+}
+
+/// A color picker with an eyedropper button next to it. Right click the color to copy its value.
+/// `id_salt` identifies the picker which picked colors go to, it must be unique in the toolbar.
+pub fn color_edit(ui: &mut Ui, id_salt: impl Hash, color: &mut Rgba) -> Response {
+    let id = ui.make_persistent_id(id_salt);
+    ui.horizontal(|ui| {
+        let mut state = load_state(ui.ctx());
+        let pass = ui.ctx().cumulative_pass_nr();
+
+        if let Some((target, picked)) = state.picked
+            && target == id
+        {
+            *color = picked;
+            state.picked = None;
+        }
+
+        let mut resp = color_edit_button_rgba(ui, color, Alpha::OnlyBlend);
+        resp.context_menu(|ui| {
+            let color32 = Color32::from(*color);
+            if ui.button(format!("Copy {}", hex(color32))).clicked() {
+                ui.ctx().copy_text(hex(color32));
+                ui.close();
+            }
+            if ui.button(format!("Copy {}", rgb(color32))).clicked() {
+                ui.ctx().copy_text(rgb(color32));
+                ui.close();
+            }
+        });
+        if resp.changed() {
+            state.edited = Some((*color).into());
+        }
+
+        let dropper = Button::new("💧")
+            .selected(state.active && state.target == Some(id))
+            .ui(ui)
+            .on_hover_text("Pick a color from the screenshot (I)");
+        if dropper.clicked() {
+            state.active = !(state.active && state.target == Some(id));
+            state.target = Some(id);
+        }
+
+        // the first picker shown takes over if the last used one is gone
+        let target_gone = state.target_pass + 1 < pass;
+        if resp.clicked() || resp.changed() || state.target.is_none() || target_gone {
+            state.target = Some(id);
+        }
+        if state.target == Some(id) {
+            state.target_pass = pass;
+            state.target_color = (*color).into();
+        }
+
+        if let Some(edited) = state.edited
+            && !ui.input(|i| i.pointer.any_down())
+        {
+            PALETTE.lock().remember(edited);
+            state.edited = None;
+        }
+        store_state(ui.ctx(), state);
+
+        resp |= dropper;
+        resp
+    })
+    .inner
+}
+
+/// The favorite and recent colors as rows of the toolbar grid, a click on a swatch applies it to
+/// the last used picker. Nothing is shown if there is no picker in the toolbar.
+pub fn palette_ui(ui: &mut Ui) {
+    let mut state = load_state(ui.ctx());
+    let Some(target) = state.target else {
+        return;
+    };
+    if state.target_pass != ui.ctx().cumulative_pass_nr() {
+        return;
+    }
+
+    let mut palette = PALETTE.lock();
+    let mut picked = None;
+
+    Label::new("Favorites").selectable(false).ui(ui);
+    ui.horizontal_wrapped(|ui| {
+        let mut removed = None;
+        for (i, color) in palette.favorites.iter().enumerate() {
+            let resp =
+                swatch(ui, *color).on_hover_text(format!("{}\nRight click to remove", hex(*color)));
+            if resp.clicked() {
+                picked = Some(*color);
+            }
+            if resp.secondary_clicked() {
+                removed = Some(i);
+            }
+        }
+        if let Some(i) = removed {
+            palette.favorites.remove(i);
+            palette.dirty = true;
+        }
+        let add_btn = Button::new("+")
+            .ui(ui)
+            .on_hover_text("Add the current color");
+        if add_btn.clicked() && !palette.favorites.contains(&state.target_color) {
+            palette.favorites.push(state.target_color);
+            palette.dirty = true;
+        }
+    });
+    ui.end_row();
+
+    if !palette.recent.is_empty() {
+        Label::new("Recent").selectable(false).ui(ui);
+        ui.horizontal_wrapped(|ui| {
+            for color in &palette.recent {
+                if swatch(ui, *color).on_hover_text(hex(*color)).clicked() {
+                    picked = Some(*color);
+                }
+            }
+        });
+        ui.end_row();
+    }
+
+    if let Some(color) = picked {
+        palette.remember(color);
+        state.picked = Some((target, color.into()));
+        store_state(ui.ctx(), state);
+        ui.ctx().request_repaint();
+    }
+}
+
+fn swatch(ui: &mut Ui, color: Color32) -> Response {
+    let size = ui.spacing().interact_size.y;
+    let (rect, resp) = ui.allocate_exact_size(Vec2::splat(size), Sense::click());
+    let visuals = ui.style().interact(&resp);
+    ui.painter().rect_filled(rect, visuals.corner_radius, color);
+    ui.painter().rect_stroke(
+        rect,
+        visuals.corner_radius,
+        Stroke::new(visuals.bg_stroke.width.max(1f32), visuals.bg_stroke.color),
+        StrokeKind::Inside,
+    );
+    resp
+}
+
+pub fn eyedropper_active(ctx: &Context) -> bool {
+    load_state(ctx).active
+}
+
+/// Start the eyedropper for the last used picker, or stop it.
+pub fn toggle_eyedropper(ctx: &Context) {
+    let mut state = load_state(ctx);
+    state.active = !state.active;
+    store_state(ctx, state);
+}
+
+/// Hand the sampled color to the picker and stop the eyedropper.
+pub fn eyedropper_pick(ctx: &Context, color: Color32) {
+    let mut state = load_state(ctx);
+    state.active = false;
+    if let Some(target) = state.target {
+        state.picked = Some((target, color.into()));
+        PALETTE.lock().remember(color);
+    }
+    if state.copy {
+        ctx.copy_text(hex(color));
+    }
+    store_state(ctx, state);
+}
+
+/// The options shown while the eyedropper is active.
+pub fn eyedropper_ui(ui: &mut Ui) {
+    let mut state = load_state(ui.ctx());
+    ui.horizontal(|ui| {
+        ui.label("Click to pick a color, Esc to cancel.");
+        Checkbox::new(&mut state.copy, "Copy hex value").ui(ui);
+        if ui.button("Cancel").clicked() {
+            state.active = false;
+        }
+    });
+    store_state(ui.ctx(), state);
+}
+
+pub fn hex(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == u8::MAX {
+        format!("#{r:02x}{g:02x}{b:02x}")
+    } else {
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+}
+
+pub fn rgb(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == u8::MAX {
+        format!("rgb({r}, {g}, {b})")
+    } else {
+        format!("rgba({r}, {g}, {b}, {:.2})", a as f32 / 255f32)
+    }
+}
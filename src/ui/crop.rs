@@ -1,28 +1,303 @@
-use eframe::egui::{Color32, CornerRadius, Pos2, Rect, Stroke, StrokeKind, Ui};
+use std::str::FromStr;
 
-use crate::ui::{move_resize::MoveResize, utils::from_ratio_rect, window::RenderInfo};
+use eframe::egui::{
+    self, Color32, ColorImage, CornerRadius, DragValue, FontId, Label, Mesh, Pos2, Rect, Rgba,
+    Slider, Stroke, StrokeKind, Ui, Vec2, Widget,
+};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
 
-const FULL_RECT: Rect = Rect {
-    min: Pos2 { x: 0f32, y: 0f32 },
-    max: Pos2 { x: 1f32, y: 1f32 },
+use crate::ui::{
+    color::color_edit,
+    move_resize::MoveResize,
+    shape::stroke::{ellipse_points, rounded_rect_points},
+    utils::{round_rect, to_image_pos, to_image_rect, to_screen_pos, to_screen_rect},
+    window::RenderInfo,
 };
 
+/// Covers the whole image, whatever its size.
+const FULL_RECT: Rect = Rect::EVERYTHING;
+/// The number of scanlines per pixel row when masking a lasso.
+const LASSO_SUBSAMPLES: usize = 4;
+
+/// The outline of the crop, pixels outside of it are transparent or filled in the export.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, EnumIter, IntoStaticStr)]
+pub enum CropShape {
+    #[default]
+    Rectangle,
+    Rounded,
+    Ellipse,
+    /// A freehand path traced by dragging, the crop is its bounding box.
+    Lasso,
+}
+
+/// The width to height ratio the crop is locked to.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, EnumIter, IntoStaticStr)]
+pub enum CropRatio {
+    #[default]
+    Free,
+    #[strum(serialize = "1:1")]
+    Square,
+    #[strum(serialize = "4:3")]
+    Standard,
+    #[strum(serialize = "16:9")]
+    Wide,
+    Custom,
+}
+
+impl CropRatio {
+    /// The width to height ratio of a preset.
+    fn value(self) -> Option<f32> {
+        match self {
+            CropRatio::Square => Some(1f32),
+            CropRatio::Standard => Some(4f32 / 3f32),
+            CropRatio::Wide => Some(16f32 / 9f32),
+            CropRatio::Free | CropRatio::Custom => None,
+        }
+    }
+}
+
+/// A crop range in image pixels, written as `x,y WxH` like slurp prints it, as `x,y,WxH`, or as
+/// an X geometry `WxH+x+y` like slop prints it.
+#[derive(Clone, Copy, Debug)]
+pub struct CropGeometry {
+    pub range: Rect,
+}
+
+impl FromStr for CropGeometry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (pos, size) = if let Some((size, pos)) = s.split_once('+') {
+            let (x, y) = pos
+                .split_once('+')
+                .ok_or("expected the position as +x+y, e.g. 640x480+100+200")?;
+            ((x, y), size)
+        } else {
+            let (pos, size) = s
+                .rsplit_once([',', ' '])
+                .ok_or("expected x,y WxH, e.g. 100,200 640x480")?;
+            let pos = pos
+                .split_once(',')
+                .ok_or("expected the position as x,y, e.g. 100,200")?;
+            (pos, size)
+        };
+        let (x, y) = pos;
+        let (width, height) = size
+            .split_once('x')
+            .ok_or("expected the size as WxH, e.g. 640x480")?;
+        let x: i32 = x.trim().parse().map_err(|_| format!("invalid x {x:?}"))?;
+        let y: i32 = y.trim().parse().map_err(|_| format!("invalid y {y:?}"))?;
+        let width: u32 = width
+            .parse()
+            .map_err(|_| format!("invalid width {width:?}"))?;
+        let height: u32 = height
+            .parse()
+            .map_err(|_| format!("invalid height {height:?}"))?;
+        if width == 0 || height == 0 {
+            return Err("the crop must not be empty".to_string());
+        }
+        Ok(CropGeometry {
+            range: Rect::from_min_size(
+                Pos2::new(x as f32, y as f32),
+                Vec2::new(width as f32, height as f32),
+            ),
+        })
+    }
+}
+
+/// A width to height ratio, written as `W:H`, e.g. `16:9`.
+#[derive(Clone, Copy, Debug)]
+pub struct AspectRatio {
+    pub size: Vec2,
+}
+
+impl FromStr for AspectRatio {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s.split_once(':').ok_or("expected W:H, e.g. 16:9")?;
+        let parse = |value: &str| {
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite() && *value > 0f32)
+                .ok_or_else(|| format!("invalid ratio {value:?}"))
+        };
+        Ok(AspectRatio {
+            size: Vec2::new(parse(width)?, parse(height)?),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CropAttribute {
+    pub shape: CropShape,
+    pub ratio: CropRatio,
+    /// The width and height of the custom ratio.
+    pub custom_ratio: Vec2,
+    /// The radius of rounded corners in image pixels.
+    pub corner_radius: f32,
+    /// The color of the export outside of the shape.
+    pub background: Rgba,
+}
+
+impl Default for CropAttribute {
+    fn default() -> Self {
+        Self {
+            shape: CropShape::Rectangle,
+            ratio: CropRatio::Free,
+            custom_ratio: Vec2::new(3f32, 2f32),
+            corner_radius: 24f32,
+            background: Rgba::TRANSPARENT,
+        }
+    }
+}
+
+impl CropAttribute {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Shape").selectable(false).ui(ui);
+        ui.horizontal(|ui| {
+            for shape in CropShape::iter() {
+                ui.selectable_value(&mut self.shape, shape, <&'static str>::from(shape));
+            }
+        });
+        ui.end_row();
+
+        if self.shape != CropShape::Lasso {
+            Label::new("Ratio").selectable(false).ui(ui);
+            ui.horizontal(|ui| {
+                for ratio in CropRatio::iter() {
+                    ui.selectable_value(&mut self.ratio, ratio, <&'static str>::from(ratio));
+                }
+            });
+            ui.end_row();
+        }
+
+        if self.shape != CropShape::Lasso && self.ratio == CropRatio::Custom {
+            Label::new("Custom ratio").selectable(false).ui(ui);
+            ui.horizontal(|ui| {
+                DragValue::new(&mut self.custom_ratio.x)
+                    .range(1f32..=100f32)
+                    .ui(ui);
+                Label::new(":").selectable(false).ui(ui);
+                DragValue::new(&mut self.custom_ratio.y)
+                    .range(1f32..=100f32)
+                    .ui(ui);
+            });
+            ui.end_row();
+        }
+
+        if self.shape == CropShape::Rounded {
+            Label::new("Corner radius").selectable(false).ui(ui);
+            Slider::new(&mut self.corner_radius, 0f32..=200f32)
+                .suffix(" px")
+                .ui(ui);
+            ui.end_row();
+        }
+
+        if self.shape != CropShape::Rectangle {
+            Label::new("Outside").selectable(false).ui(ui);
+            color_edit(ui, "crop_background", &mut self.background);
+            ui.end_row();
+        }
+    }
+
+    /// The width to height ratio resizing keeps, if any.
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        if self.shape == CropShape::Lasso {
+            return None;
+        }
+        match self.ratio {
+            CropRatio::Custom => Some(self.custom_ratio.x / self.custom_ratio.y),
+            ratio => ratio.value(),
+        }
+    }
+
+    /// The radii of the corners of a crop of `size`, an ellipse is all corners.
+    fn corner_radii(&self, size: Vec2) -> Vec2 {
+        match self.shape {
+            CropShape::Rectangle | CropShape::Lasso => Vec2::ZERO,
+            CropShape::Rounded => Vec2::splat(self.corner_radius.min(size.min_elem() / 2f32)),
+            CropShape::Ellipse => size / 2f32,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct CropTool {
+    /// The cropped range in image pixels.
     pub cropped_range: Rect,
+    pub attributes: CropAttribute,
     move_resize: MoveResize,
+    /// The closed lasso path in image pixels.
+    lasso: Vec<Pos2>,
+    /// Whether the lasso is being traced.
+    lassoing: bool,
 }
 
 impl Default for CropTool {
     fn default() -> Self {
         Self {
             cropped_range: FULL_RECT,
+            attributes: Default::default(),
             move_resize: Default::default(),
+            lasso: Vec::new(),
+            lassoing: false,
         }
     }
 }
 
 impl CropTool {
+    /// A crop preset from the command line, a ratio which matches a preset selects it.
+    pub fn new(geometry: Option<CropGeometry>, ratio: Option<AspectRatio>) -> Self {
+        let mut crop_tool = Self::default();
+        if let Some(geometry) = geometry {
+            crop_tool.cropped_range = geometry.range;
+        }
+        if let Some(AspectRatio { size }) = ratio {
+            let value = size.x / size.y;
+            crop_tool.attributes.ratio = CropRatio::iter()
+                .find(|ratio| ratio.value().is_some_and(|v| (v - value).abs() < 1e-3))
+                .unwrap_or(CropRatio::Custom);
+            crop_tool.attributes.custom_ratio = size;
+            if let Some(geometry) = geometry {
+                crop_tool.cropped_range = fit_aspect_ratio(geometry.range, value);
+            }
+        }
+        crop_tool
+    }
+
+    /// Keep the part of the crop inside of an image of `image_size` and fit the locked ratio
+    /// into it again, the crop is removed if nothing is left of it.
+    pub fn clamp_to_image(&mut self, image_size: Vec2) -> Result<(), String> {
+        if self.cropped_range == FULL_RECT {
+            return Ok(());
+        }
+        let range = self
+            .cropped_range
+            .intersect(Rect::from_min_size(Pos2::ZERO, image_size));
+        if !range.is_positive() {
+            let crop = self.cropped_range;
+            self.cropped_range = FULL_RECT;
+            self.lasso.clear();
+            return Err(format!(
+                "The crop {},{} {}x{} lies outside of the {}x{} screenshot.",
+                crop.left(),
+                crop.top(),
+                crop.width(),
+                crop.height(),
+                image_size.x,
+                image_size.y
+            ));
+        }
+        self.cropped_range = match self.attributes.aspect_ratio() {
+            Some(aspect_ratio) => fit_aspect_ratio(range, aspect_ratio),
+            None => range,
+        };
+        Ok(())
+    }
+
     pub fn on_global_response(
         &mut self,
         ui: &mut Ui,
@@ -31,31 +306,190 @@ impl CropTool {
     ) {
         if resp.clicked()
             && let Some(clicked_pos) = resp.interact_pointer_pos()
-            && !from_ratio_rect(&self.cropped_range, &render_info.screenshot_rect)
-                .contains(clicked_pos)
+            && !self.render_range(render_info).contains(clicked_pos)
         {
             self.cropped_range = FULL_RECT;
+            self.lasso.clear();
+        }
+        if self.attributes.shape == CropShape::Lasso {
+            self.handle_lasso(resp, render_info);
+        } else {
+            self.move_resize.aspect_ratio = self.attributes.aspect_ratio();
+            self.move_resize.handle_resize(
+                ui,
+                resp,
+                render_info,
+                &mut self.cropped_range,
+                super::move_resize::ResizeMode::Cursor,
+            );
         }
-        self.move_resize.handle_resize(
-            ui,
-            resp,
-            render_info,
-            &mut self.cropped_range,
-            super::move_resize::ResizeMode::Cursor,
-        );
     }
 
-    pub fn ui(&mut self, ui: &mut Ui, render_info: &RenderInfo, active: bool) {
-        if self.cropped_range == FULL_RECT {
+    /// The crop attributes, and the position and size of the crop in image pixels.
+    pub fn toolbar_ui(&mut self, ui: &mut Ui) {
+        let aspect_ratio = self.attributes.aspect_ratio();
+        self.attributes.ui(ui);
+        let new_aspect_ratio = self.attributes.aspect_ratio();
+        if self.cropped_range == FULL_RECT || self.attributes.shape == CropShape::Lasso {
+            return;
+        }
+        if new_aspect_ratio != aspect_ratio
+            && let Some(aspect_ratio) = new_aspect_ratio
+        {
+            self.cropped_range = fit_aspect_ratio(self.cropped_range, aspect_ratio);
+        }
+
+        let mut min = self.cropped_range.min;
+        let mut size = self.cropped_range.size();
+        Label::new("Position").selectable(false).ui(ui);
+        ui.horizontal(|ui| {
+            DragValue::new(&mut min.x)
+                .prefix("x: ")
+                .suffix(" px")
+                .ui(ui);
+            DragValue::new(&mut min.y)
+                .prefix("y: ")
+                .suffix(" px")
+                .ui(ui);
+        });
+        ui.end_row();
+
+        Label::new("Size").selectable(false).ui(ui);
+        ui.horizontal(|ui| {
+            let width = DragValue::new(&mut size.x)
+                .range(1f32..=f32::INFINITY)
+                .prefix("w: ")
+                .suffix(" px")
+                .ui(ui);
+            let height = DragValue::new(&mut size.y)
+                .range(1f32..=f32::INFINITY)
+                .prefix("h: ")
+                .suffix(" px")
+                .ui(ui);
+            // the other side follows a locked ratio
+            if let Some(aspect_ratio) = new_aspect_ratio {
+                if width.changed() {
+                    size.y = size.x / aspect_ratio;
+                } else if height.changed() {
+                    size.x = size.y * aspect_ratio;
+                }
+            }
+        });
+        ui.end_row();
+        self.cropped_range = round_rect(Rect::from_min_size(min, size));
+    }
+
+    /// Trace a new lasso while dragging, the crop becomes its bounding box once it is closed.
+    fn handle_lasso(&mut self, resp: &eframe::egui::Response, render_info: &RenderInfo) {
+        if resp.drag_started() {
+            self.lasso.clear();
+            self.lassoing = true;
+        }
+        if !self.lassoing {
+            return;
+        }
+        if resp.dragged()
+            && let Some(pos) = resp.interact_pointer_pos()
+        {
+            let pos = to_image_pos(&pos, &render_info.view);
+            if self
+                .lasso
+                .last()
+                .is_none_or(|last| last.distance(pos) >= 1f32)
+            {
+                self.lasso.push(pos);
+            }
+        }
+        if resp.drag_stopped() {
+            self.lassoing = false;
+            self.close_lasso();
+        }
+    }
+
+    /// Crop to the bounding box of the traced lasso, a lasso which encloses less than a pixel is
+    /// dropped instead of masking out everything.
+    fn close_lasso(&mut self) {
+        if self.lasso.len() < 3 || path_area(&self.lasso) < 1f32 {
+            self.lasso.clear();
+            return;
+        }
+        let bounds = Rect::from_points(&self.lasso);
+        self.cropped_range = Rect::from_min_max(bounds.min.floor(), bounds.max.ceil());
+    }
+
+    /// The cropped range on the screen, the whole screenshot if nothing is cropped.
+    pub fn render_range(&self, render_info: &RenderInfo) -> Rect {
+        to_screen_rect(&self.cropped_range, &render_info.view)
+            .intersect(render_info.screenshot_rect)
+    }
+
+    /// Replace the pixels of the exported crop outside of its shape with the background, with
+    /// antialiased edges. `origin` is the top left of the export in image pixels.
+    pub fn apply_mask(&self, image: &mut ColorImage, origin: Pos2) {
+        let background: Color32 = self.attributes.background.into();
+        let width = image.width();
+        if self.attributes.shape == CropShape::Lasso {
+            if self.lasso.len() < 3 {
+                return;
+            }
+            let lasso: Vec<Pos2> = self
+                .lasso
+                .iter()
+                .map(|pos| *pos - origin.to_vec2())
+                .collect();
+            for (y, row) in image.pixels.chunks_exact_mut(width).enumerate() {
+                let coverage = lasso_coverage(&lasso, y, width);
+                for (pixel, coverage) in row.iter_mut().zip(coverage) {
+                    if coverage < 1f32 {
+                        *pixel = background.lerp_to_gamma(*pixel, coverage);
+                    }
+                }
+            }
+            return;
+        }
+        let size = Vec2::new(image.width() as f32, image.height() as f32);
+        let radii = self.attributes.corner_radii(size);
+        if radii == Vec2::ZERO {
             return;
         }
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            let center = Pos2::new((i % width) as f32 + 0.5f32, (i / width) as f32 + 0.5f32);
+            let coverage = (0.5f32 - signed_distance(center, size, radii)).clamp(0f32, 1f32);
+            if coverage < 1f32 {
+                *pixel = background.lerp_to_gamma(*pixel, coverage);
+            }
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, render_info: &RenderInfo, active: bool) {
         const STROKE_COLOR: Color32 = Color32::from_gray(0xee);
         const FILL_COLOR: Color32 = Color32::from_rgba_premultiplied(0x30, 0x30, 0x30, 0x90);
         let stroke = Stroke {
             width: 2.0,
             color: STROKE_COLOR,
         };
-        let render_range = from_ratio_rect(&self.cropped_range, &render_info.screenshot_rect);
+        let lasso: Vec<Pos2> = self
+            .lasso
+            .iter()
+            .map(|pos| to_screen_pos(pos, &render_info.view))
+            .collect();
+        if self.lassoing {
+            if lasso.len() >= 2 {
+                dimension_label(ui, Rect::from_points(&lasso), render_info);
+            }
+            ui.painter().add(egui::Shape::line(lasso, stroke));
+            return;
+        }
+        let uncropped = self.cropped_range == FULL_RECT;
+        if uncropped
+            && matches!(
+                self.attributes.shape,
+                CropShape::Rectangle | CropShape::Lasso
+            )
+        {
+            return;
+        }
+        let render_range = self.render_range(render_info);
 
         // draw the shadow
         ui.painter().rect_filled(
@@ -104,16 +538,496 @@ impl CropTool {
             FILL_COLOR,
         );
         // draw the border
-        ui.painter().rect_stroke(
-            render_range,
-            CornerRadius::ZERO,
-            stroke,
-            StrokeKind::Outside,
-        );
+        // the radii are in image pixels, like the mask of the export
+        let image_range = to_image_rect(&render_range, &render_info.view);
+        let radii = self.attributes.corner_radii(image_range.size()) * render_info.pixel_ratio;
+        let outline = match self.attributes.shape {
+            CropShape::Lasso if lasso.len() >= 3 => Some(lasso),
+            CropShape::Rounded if radii != Vec2::ZERO => {
+                Some(rounded_rect_points(render_range, radii.x))
+            }
+            CropShape::Ellipse => Some(ellipse_points(render_range.center(), radii)),
+            _ => None,
+        };
+        if let Some(outline) = outline {
+            // shade the crop outside of the outline, one screen point high row by row
+            let mut mesh = Mesh::default();
+            let mut top = render_range.top();
+            while top < render_range.bottom() {
+                let bottom = (top + 1f32).min(render_range.bottom());
+                let crossings = path_crossings(&outline, (top + bottom) / 2f32);
+                let mut left = render_range.left();
+                for pair in crossings.chunks_exact(2) {
+                    let [start, end] = [pair[0], pair[1]]
+                        .map(|x| x.clamp(render_range.left(), render_range.right()));
+                    mesh.add_colored_rect(
+                        Rect::from_x_y_ranges(left..=start.max(left), top..=bottom),
+                        FILL_COLOR,
+                    );
+                    left = end.max(left);
+                }
+                mesh.add_colored_rect(
+                    Rect::from_x_y_ranges(left..=render_range.right(), top..=bottom),
+                    FILL_COLOR,
+                );
+                top = bottom;
+            }
+            ui.painter().add(mesh);
+            ui.painter().add(egui::Shape::closed_line(outline, stroke));
+        } else {
+            ui.painter().rect_stroke(
+                render_range,
+                CornerRadius::ZERO,
+                stroke,
+                StrokeKind::Outside,
+            );
+        }
         // draw the handles
-        if active {
+        if active && !uncropped && self.attributes.shape != CropShape::Lasso {
+            self.move_resize.aspect_ratio = self.attributes.aspect_ratio();
             self.move_resize
                 .ui(ui, render_info, &mut self.cropped_range);
         }
+        if self.move_resize.is_dragging() {
+            dimension_label(ui, self.render_range(render_info), render_info);
+        }
+    }
+}
+
+/// Show the size and position of `render_range` in image pixels below it, or inside of it at the
+/// bottom of the screen.
+fn dimension_label(ui: &Ui, render_range: Rect, render_info: &RenderInfo) {
+    let range = round_rect(to_image_rect(&render_range, &render_info.view));
+    let painter = ui.painter();
+    let galley = painter.layout_no_wrap(
+        format!(
+            "{} × {}  ({}, {})",
+            range.width(),
+            range.height(),
+            range.left(),
+            range.top()
+        ),
+        FontId::monospace(12f32),
+        Color32::WHITE,
+    );
+    let size = galley.size() + Vec2::splat(8f32);
+    let mut min = render_range.left_bottom() + Vec2::new(0f32, 6f32);
+    if min.y + size.y > ui.clip_rect().bottom() {
+        min.y = render_range.bottom() - size.y - 6f32;
+    }
+    let label_range = Rect::from_min_size(min, size);
+    painter.rect_filled(label_range, 4f32, Color32::from_black_alpha(200));
+    painter.galley(label_range.min + Vec2::splat(4f32), galley, Color32::WHITE);
+}
+
+/// The largest rect of `aspect_ratio` in `rect` around its center, in whole pixels.
+fn fit_aspect_ratio(rect: Rect, aspect_ratio: f32) -> Rect {
+    let size = if rect.aspect_ratio() > aspect_ratio {
+        Vec2::new(rect.height() * aspect_ratio, rect.height())
+    } else {
+        Vec2::new(rect.width(), rect.width() / aspect_ratio)
+    };
+    round_rect(Rect::from_center_size(rect.center(), size))
+}
+
+/// The approximate distance from `pos` to the outline of a rect of `size` at the origin with
+/// elliptic corners of `radii`, negative inside.
+fn signed_distance(pos: Pos2, size: Vec2, radii: Vec2) -> f32 {
+    let half = size / 2f32;
+    // the offset into the corner of the quadrant of pos
+    let q = (pos.to_vec2() - half).abs() - (half - radii);
+    if q.x <= 0f32 || q.y <= 0f32 || radii.min_elem() <= 0f32 {
+        return (q - radii).max_elem();
+    }
+    // first order distance to the ellipse, the value divided by the length of its gradient
+    let k = (q / radii).length();
+    let gradient = (q / (radii * radii)).length();
+    (k - 1f32) * k / gradient
+}
+
+/// The sorted positions where the horizontal line at `y` crosses the closed `path`, the line is
+/// inside of the path between each pair of them.
+fn path_crossings(path: &[Pos2], y: f32) -> Vec<f32> {
+    let mut crossings: Vec<f32> = path
+        .iter()
+        .zip(path.iter().cycle().skip(1))
+        .filter(|(a, b)| (a.y <= y) != (b.y <= y))
+        .map(|(a, b)| a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y))
+        .collect();
+    crossings.sort_by(f32::total_cmp);
+    crossings
+}
+
+/// The area enclosed by the closed `path`, parts which wind the other way subtract from it.
+fn path_area(path: &[Pos2]) -> f32 {
+    let twice_area: f32 = path
+        .iter()
+        .zip(path.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+    twice_area.abs() / 2f32
+}
+
+/// The share of each pixel of the row `y` which is inside of the closed `path`.
+fn lasso_coverage(path: &[Pos2], y: usize, width: usize) -> Vec<f32> {
+    let mut coverage = vec![0f32; width];
+    let weight = 1f32 / LASSO_SUBSAMPLES as f32;
+    for i in 0..LASSO_SUBSAMPLES {
+        let crossings = path_crossings(path, y as f32 + (i as f32 + 0.5f32) * weight);
+        for pair in crossings.chunks_exact(2) {
+            let (start, end) = (pair[0].max(0f32), pair[1].min(width as f32));
+            if start >= end {
+                continue;
+            }
+            for (x, coverage) in coverage
+                .iter_mut()
+                .enumerate()
+                .take(end.ceil() as usize)
+                .skip(start.floor() as usize)
+            {
+                let overlap = end.min(x as f32 + 1f32) - start.max(x as f32);
+                *coverage += overlap * weight;
+            }
+        }
+    }
+    coverage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect::from_min_size(Pos2::new(x, y), Vec2::new(width, height))
+    }
+
+    #[test]
+    fn parse_crop_geometry() {
+        for geometry in ["100,200 640x480", "100,200,640x480", "640x480+100+200"] {
+            let crop: CropGeometry = geometry.parse().unwrap();
+            assert_eq!(crop.range, rect(100f32, 200f32, 640f32, 480f32));
+        }
+        let crop: CropGeometry = " -10,-20 30x40\n".parse().unwrap();
+        assert_eq!(crop.range, rect(-10f32, -20f32, 30f32, 40f32));
+        let crop: CropGeometry = "30x40+-10+-20".parse().unwrap();
+        assert_eq!(crop.range, rect(-10f32, -20f32, 30f32, 40f32));
+
+        for geometry in [
+            "",
+            "garbage",
+            "100,200",
+            "640x480",
+            "100,200 0x480",
+            "100,200 640x0",
+            "100,200 -640x480",
+            "100,200 640x-480",
+            "0x480+100+200",
+            "640x480+100",
+            "a,200 640x480",
+            "100,200 640xb",
+            "100,200 640x480x2",
+        ] {
+            assert!(
+                geometry.parse::<CropGeometry>().is_err(),
+                "{geometry:?} is accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_aspect_ratio() {
+        let ratio: AspectRatio = "16:9".parse().unwrap();
+        assert_eq!(ratio.size, Vec2::new(16f32, 9f32));
+        let ratio: AspectRatio = "2.35:1".parse().unwrap();
+        assert_eq!(ratio.size, Vec2::new(2.35f32, 1f32));
+
+        for ratio in [
+            "", "16", "16/9", "0:9", "16:0", "-16:9", "16:-9", "a:b", "inf:1", "NaN:1",
+        ] {
+            assert!(
+                ratio.parse::<AspectRatio>().is_err(),
+                "{ratio:?} is accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn crop_ratio_from_the_command_line() {
+        let ratio = "4:3".parse().ok();
+        let crop_tool = CropTool::new(None, ratio);
+        assert_eq!(crop_tool.attributes.ratio, CropRatio::Standard);
+        assert_eq!(crop_tool.cropped_range, FULL_RECT);
+
+        let crop_tool = CropTool::new(None, "3:2".parse().ok());
+        assert_eq!(crop_tool.attributes.ratio, CropRatio::Custom);
+        assert_eq!(crop_tool.attributes.aspect_ratio(), Some(1.5f32));
+
+        let crop_tool = CropTool::new("0,0 100x100".parse().ok(), "2:1".parse().ok());
+        assert_eq!(crop_tool.cropped_range, rect(0f32, 25f32, 100f32, 50f32));
+    }
+
+    #[test]
+    fn clamped_crop_keeps_its_ratio() {
+        // half of the crop hangs over the right edge
+        let mut crop_tool = CropTool::new("150,0 100x100".parse().ok(), "1:1".parse().ok());
+        crop_tool.clamp_to_image(Vec2::new(200f32, 100f32)).unwrap();
+        assert_eq!(crop_tool.cropped_range, rect(150f32, 25f32, 50f32, 50f32));
+
+        let mut crop_tool = CropTool::new("-60,-20 160x90".parse().ok(), "16:9".parse().ok());
+        crop_tool.clamp_to_image(Vec2::new(320f32, 180f32)).unwrap();
+        let range = crop_tool.cropped_range;
+        assert!(Rect::from_min_size(Pos2::ZERO, Vec2::new(320f32, 180f32)).contains_rect(range));
+        assert!((range.aspect_ratio() - 16f32 / 9f32).abs() < 0.05f32);
+
+        let mut crop_tool = CropTool::new("150,0 100x100".parse().ok(), None);
+        crop_tool.clamp_to_image(Vec2::new(200f32, 100f32)).unwrap();
+        assert_eq!(crop_tool.cropped_range, rect(150f32, 0f32, 50f32, 100f32));
+
+        let mut crop_tool = CropTool::new("300,0 100x100".parse().ok(), None);
+        assert!(crop_tool.clamp_to_image(Vec2::new(200f32, 100f32)).is_err());
+        assert_eq!(crop_tool.cropped_range, FULL_RECT);
+
+        let mut crop_tool = CropTool::new(None, "1:1".parse().ok());
+        crop_tool.clamp_to_image(Vec2::new(200f32, 100f32)).unwrap();
+        assert_eq!(crop_tool.cropped_range, FULL_RECT);
+    }
+
+    fn mask(shape: CropShape, size: [usize; 2]) -> ColorImage {
+        let mut crop_tool = CropTool::default();
+        crop_tool.attributes.shape = shape;
+        crop_tool.attributes.corner_radius = 10f32;
+        let mut image = ColorImage::new(size, vec![Color32::RED; size[0] * size[1]]);
+        crop_tool.apply_mask(&mut image, Pos2::new(100f32, 100f32));
+        image
+    }
+
+    #[test]
+    fn rectangle_distance() {
+        let size = Vec2::new(100f32, 50f32);
+        let distance = |x, y| signed_distance(Pos2::new(x, y), size, Vec2::ZERO);
+        assert_eq!(distance(50f32, 25f32), -25f32);
+        assert_eq!(distance(5f32, 25f32), -5f32);
+        assert_eq!(distance(100f32, 25f32), 0f32);
+        assert_eq!(distance(0f32, 0f32), 0f32);
+        assert_eq!(distance(110f32, 25f32), 10f32);
+        assert_eq!(distance(50f32, -5f32), 5f32);
+    }
+
+    #[test]
+    fn rounded_distance() {
+        let size = Vec2::splat(100f32);
+        let radii = Vec2::splat(20f32);
+        let distance = |pos: Pos2| signed_distance(pos, size, radii);
+        assert_eq!(distance(Pos2::new(50f32, 50f32)), -50f32);
+        assert_eq!(distance(Pos2::new(50f32, 0f32)), 0f32);
+        assert_eq!(distance(Pos2::new(5f32, 50f32)), -5f32);
+        assert_eq!(distance(Pos2::new(-5f32, 50f32)), 5f32);
+        // the corners are circles around 20,20 and its mirrors
+        for corner in [
+            Pos2::new(20f32, 20f32),
+            Pos2::new(80f32, 20f32),
+            Pos2::new(20f32, 80f32),
+            Pos2::new(80f32, 80f32),
+        ] {
+            let outward = (corner - Pos2::new(50f32, 50f32)).normalized();
+            assert!(distance(corner + outward * 20f32).abs() < 1e-3);
+            assert!((distance(corner + outward * 15f32) + 5f32).abs() < 1e-3);
+            assert!((distance(corner + outward * 25f32) - 5f32).abs() < 1e-3);
+        }
+        // the image corner is cut off
+        assert!((distance(Pos2::ZERO) - 20f32 * (2f32.sqrt() - 1f32)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ellipse_distance() {
+        let size = Vec2::new(200f32, 100f32);
+        let distance = |x: f32, y: f32| signed_distance(Pos2::new(x, y), size, size / 2f32);
+        assert_eq!(distance(100f32, 50f32), -50f32);
+        assert_eq!(distance(0f32, 50f32), 0f32);
+        assert_eq!(distance(100f32, 0f32), 0f32);
+        assert_eq!(distance(210f32, 50f32), 10f32);
+        for angle in [0.3f32, 1f32, 2f32, 4f32, 5.5f32] {
+            let pos = Pos2::new(100f32, 50f32) + Vec2::angled(angle) * size / 2f32;
+            assert!(distance(pos.x, pos.y).abs() < 1e-3, "{pos:?}");
+            let inside = Pos2::new(100f32, 50f32) + Vec2::angled(angle) * size / 2.5f32;
+            assert!(distance(inside.x, inside.y) < 0f32, "{inside:?}");
+            let outside = Pos2::new(100f32, 50f32) + Vec2::angled(angle) * size / 1.5f32;
+            assert!(distance(outside.x, outside.y) > 0f32, "{outside:?}");
+        }
+        assert!(distance(0f32, 0f32) > 0f32);
+    }
+
+    #[test]
+    fn rectangle_mask_keeps_every_pixel() {
+        let image = mask(CropShape::Rectangle, [40, 30]);
+        assert!(image.pixels.iter().all(|pixel| *pixel == Color32::RED));
+    }
+
+    #[test]
+    fn rounded_and_ellipse_masks() {
+        for shape in [CropShape::Rounded, CropShape::Ellipse] {
+            let image = mask(shape, [40, 30]);
+            let at = |x: usize, y: usize| image.pixels[y * 40 + x];
+            for (x, y) in [(0, 0), (39, 0), (0, 29), (39, 29)] {
+                assert_eq!(at(x, y), Color32::TRANSPARENT, "{shape:?} {x},{y}");
+            }
+            for (x, y) in [(20, 15), (20, 2), (2, 15), (37, 15), (20, 27)] {
+                assert_eq!(at(x, y), Color32::RED, "{shape:?} {x},{y}");
+            }
+            // the edge is antialiased
+            assert!(
+                image
+                    .pixels
+                    .iter()
+                    .any(|pixel| pixel.a() > 0 && pixel.a() < 255),
+                "{shape:?}"
+            );
+        }
+        // the radius is limited to half of the short side
+        let image = mask(CropShape::Rounded, [8, 8]);
+        assert_eq!(image.pixels[0], Color32::TRANSPARENT);
+        assert_eq!(image.pixels[4 * 8 + 4], Color32::RED);
+    }
+
+    /// A U opening to the bottom, the gap is 3.5 to 6.5 wide and reaches up to 3.
+    fn u_lasso() -> Vec<Pos2> {
+        [
+            (0f32, 0f32),
+            (10f32, 0f32),
+            (10f32, 10f32),
+            (6.5f32, 10f32),
+            (6.5f32, 3f32),
+            (3.5f32, 3f32),
+            (3.5f32, 10f32),
+            (0f32, 10f32),
+        ]
+        .map(|(x, y)| Pos2::new(x, y))
+        .to_vec()
+    }
+
+    fn lasso_mask(lasso: Vec<Pos2>, size: [usize; 2]) -> ColorImage {
+        let mut crop_tool = CropTool::default();
+        crop_tool.attributes.shape = CropShape::Lasso;
+        crop_tool.lasso = lasso;
+        crop_tool.close_lasso();
+        let mut image = ColorImage::new(size, vec![Color32::RED; size[0] * size[1]]);
+        crop_tool.apply_mask(&mut image, Pos2::ZERO);
+        image
+    }
+
+    #[test]
+    fn concave_lasso_coverage() {
+        let lasso = u_lasso();
+        assert_eq!(path_crossings(&lasso, 1f32), [0f32, 10f32]);
+        assert_eq!(path_crossings(&lasso, 5f32), [0f32, 3.5f32, 6.5f32, 10f32]);
+        assert!(path_crossings(&lasso, 11f32).is_empty());
+
+        let coverage = lasso_coverage(&lasso, 5, 12);
+        assert_eq!(
+            coverage,
+            [
+                1f32, 1f32, 1f32, 0.5f32, 0f32, 0f32, 0.5f32, 1f32, 1f32, 1f32, 0f32, 0f32
+            ]
+        );
+        assert_eq!(lasso_coverage(&lasso, 1, 12)[5], 1f32);
+
+        let image = lasso_mask(lasso, [12, 12]);
+        assert_eq!(image.pixels[12 + 5], Color32::RED);
+        assert_eq!(image.pixels[5 * 12 + 1], Color32::RED);
+        assert_eq!(image.pixels[5 * 12 + 5], Color32::TRANSPARENT);
+        assert_eq!(image.pixels[5 * 12 + 11], Color32::TRANSPARENT);
+    }
+
+    #[test]
+    fn self_intersecting_lasso_coverage() {
+        // a bow tie, the triangles left and right of the crossing are inside
+        let bow_tie = [(0f32, 0f32), (10f32, 10f32), (10f32, 0f32), (0f32, 10f32)]
+            .map(|(x, y)| Pos2::new(x, y));
+        assert_eq!(path_crossings(&bow_tie, 2f32), [0f32, 2f32, 8f32, 10f32]);
+        let coverage = lasso_coverage(&bow_tie, 2, 10);
+        assert_eq!(coverage[0], 1f32);
+        assert_eq!(coverage[9], 1f32);
+        assert_eq!(coverage[5], 0f32);
+
+        // a pentagram, the pentagon in the middle is outside of it by the even-odd rule
+        let pentagram: Vec<Pos2> = (0..5)
+            .map(|i| {
+                let angle =
+                    -std::f32::consts::FRAC_PI_2 + i as f32 * 4f32 * std::f32::consts::PI / 5f32;
+                Pos2::new(50f32, 50f32) + 50f32 * Vec2::angled(angle)
+            })
+            .collect();
+        assert_eq!(lasso_coverage(&pentagram, 50, 100)[50], 0f32);
+        assert_eq!(path_crossings(&pentagram, 50.5f32).len(), 4);
+        let image = lasso_mask(pentagram, [100, 100]);
+        assert_eq!(image.pixels[50 * 100 + 50], Color32::TRANSPARENT);
+        assert_eq!(image.pixels[10 * 100 + 50], Color32::RED);
+    }
+
+    #[test]
+    fn degenerate_lasso_keeps_the_image() {
+        for lasso in [
+            Vec::new(),
+            vec![Pos2::new(3f32, 3f32)],
+            vec![Pos2::new(3f32, 3f32), Pos2::new(8f32, 6f32)],
+            // no area
+            vec![
+                Pos2::new(1f32, 1f32),
+                Pos2::new(5f32, 5f32),
+                Pos2::new(9f32, 9f32),
+            ],
+        ] {
+            assert!(lasso_coverage(&lasso, 4, 10).iter().all(|c| *c == 0f32));
+            let image = lasso_mask(lasso.clone(), [10, 10]);
+            assert!(
+                image.pixels.iter().all(|pixel| *pixel == Color32::RED),
+                "{lasso:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fit_aspect_ratio_inside_of_the_rect() {
+        let range = rect(10f32, 20f32, 400f32, 300f32);
+        assert_eq!(
+            fit_aspect_ratio(range, 1f32),
+            rect(60f32, 20f32, 300f32, 300f32)
+        );
+        assert_eq!(
+            fit_aspect_ratio(range, 16f32 / 9f32),
+            rect(10f32, 58f32, 400f32, 225f32)
+        );
+        assert_eq!(fit_aspect_ratio(range, 4f32 / 3f32), range);
+        // a tall ratio
+        assert_eq!(
+            fit_aspect_ratio(range, 0.5f32),
+            rect(135f32, 20f32, 150f32, 300f32)
+        );
+        // whole pixels even if the ratio does not divide the size
+        let fitted = fit_aspect_ratio(rect(0f32, 0f32, 101f32, 100f32), 3f32 / 2f32);
+        assert_eq!(fitted, round_rect(fitted));
+        assert!(rect(0f32, 0f32, 101f32, 100f32).contains_rect(fitted));
+        assert!((fitted.aspect_ratio() - 1.5f32).abs() < 0.02f32);
+    }
+
+    #[test]
+    fn ratio_presets() {
+        let mut attributes = CropAttribute::default();
+        assert_eq!(attributes.ratio, CropRatio::Free);
+        assert_eq!(attributes.aspect_ratio(), None);
+        for (ratio, value) in [
+            (CropRatio::Square, 1f32),
+            (CropRatio::Standard, 4f32 / 3f32),
+            (CropRatio::Wide, 16f32 / 9f32),
+        ] {
+            attributes.ratio = ratio;
+            assert_eq!(attributes.aspect_ratio(), Some(value));
+        }
+        attributes.ratio = CropRatio::Custom;
+        attributes.custom_ratio = Vec2::new(5f32, 4f32);
+        assert_eq!(attributes.aspect_ratio(), Some(1.25f32));
+        // a lasso follows the path
+        attributes.shape = CropShape::Lasso;
+        assert_eq!(attributes.aspect_ratio(), None);
     }
 }
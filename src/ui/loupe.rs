@@ -0,0 +1,112 @@
+use eframe::egui::{
+    Color32, ColorImage, Context, FontId, Id, LayerId, Mesh, Order, Pos2, Rect, Stroke, StrokeKind,
+    Vec2,
+};
+
+use crate::ui::{color, utils::to_image_pos, window::RenderInfo};
+
+/// The number of image pixels across the loupe, odd so that the cursor pixel is in the middle.
+const LOUPE_PIXELS: i32 = 15;
+/// The size of one magnified pixel on the screen.
+const CELL_SIZE: f32 = 8f32;
+/// The distance between the cursor and the loupe.
+const CURSOR_OFFSET: f32 = 24f32;
+const GRID_STROKE: Stroke = Stroke {
+    width: 1f32,
+    color: Color32::from_rgba_premultiplied(0x20, 0x20, 0x20, 0x20),
+};
+const OUTSIDE_COLOR: Color32 = Color32::from_gray(0x20);
+
+/// The image pixel under `pos`, it may lie outside of the image.
+pub fn image_pixel(pos: Pos2, render_info: &RenderInfo) -> (i32, i32) {
+    let pos = to_image_pos(&pos, &render_info.view);
+    (pos.x.floor() as i32, pos.y.floor() as i32)
+}
+
+/// Show the pixels of `image` around `pos` magnified next to the cursor, with the image
+/// coordinates and the color of the pixel under it.
+pub fn loupe_ui(ctx: &Context, image: &ColorImage, pos: Pos2, render_info: &RenderInfo) {
+    let (x, y) = image_pixel(pos, render_info);
+    let pixel = |x: i32, y: i32| {
+        (x >= 0 && y >= 0 && (x as usize) < image.width() && (y as usize) < image.height())
+            .then(|| image[(x as usize, y as usize)])
+    };
+
+    let info = match pixel(x, y) {
+        Some(color) => format!("{x}, {y}  {}", color::hex(color)),
+        None => format!("{x}, {y}"),
+    };
+    let painter = ctx.layer_painter(LayerId::new(Order::Tooltip, Id::new("loupe")));
+    let galley = painter.layout_no_wrap(info, FontId::monospace(12f32), Color32::WHITE);
+
+    // keep the loupe on the screen, flipping it to the other side of the cursor if needed
+    let size = Vec2::new(
+        LOUPE_PIXELS as f32 * CELL_SIZE,
+        LOUPE_PIXELS as f32 * CELL_SIZE + galley.size().y + 8f32,
+    );
+    let screen = ctx.screen_rect();
+    let mut min = pos + Vec2::splat(CURSOR_OFFSET);
+    if min.x + size.x > screen.right() {
+        min.x = pos.x - CURSOR_OFFSET - size.x;
+    }
+    if min.y + size.y > screen.bottom() {
+        min.y = pos.y - CURSOR_OFFSET - size.y;
+    }
+    let frame = Rect::from_min_size(min, size);
+    let cells = Rect::from_min_size(min, Vec2::splat(LOUPE_PIXELS as f32 * CELL_SIZE));
+
+    painter.rect_filled(frame.expand(2f32), 4f32, Color32::from_black_alpha(200));
+    let mut mesh = Mesh::default();
+    let half = LOUPE_PIXELS / 2;
+    for row in 0..LOUPE_PIXELS {
+        for column in 0..LOUPE_PIXELS {
+            let cell = Rect::from_min_size(
+                cells.min + Vec2::new(column as f32, row as f32) * CELL_SIZE,
+                Vec2::splat(CELL_SIZE),
+            );
+            let color = pixel(x + column - half, y + row - half).unwrap_or(OUTSIDE_COLOR);
+            mesh.add_colored_rect(cell, color.to_opaque());
+        }
+    }
+    painter.add(mesh);
+
+    // the pixel grid
+    for i in 1..LOUPE_PIXELS {
+        let offset = i as f32 * CELL_SIZE;
+        painter.vline(cells.left() + offset, cells.y_range(), GRID_STROKE);
+        painter.hline(cells.x_range(), cells.top() + offset, GRID_STROKE);
+    }
+
+    // the crosshair around the cursor pixel
+    let center = Rect::from_min_size(
+        cells.min + Vec2::splat(half as f32 * CELL_SIZE),
+        Vec2::splat(CELL_SIZE),
+    );
+    let crosshair = Stroke::new(1f32, Color32::from_white_alpha(160));
+    painter.vline(center.center().x, cells.top()..=center.top(), crosshair);
+    painter.vline(
+        center.center().x,
+        center.bottom()..=cells.bottom(),
+        crosshair,
+    );
+    painter.hline(cells.left()..=center.left(), center.center().y, crosshair);
+    painter.hline(center.right()..=cells.right(), center.center().y, crosshair);
+    painter.rect_stroke(
+        center,
+        0f32,
+        Stroke::new(1f32, Color32::BLACK),
+        StrokeKind::Outside,
+    );
+    painter.rect_stroke(
+        center.expand(1f32),
+        0f32,
+        Stroke::new(1f32, Color32::WHITE),
+        StrokeKind::Outside,
+    );
+
+    painter.galley(
+        Pos2::new(cells.left() + 4f32, cells.bottom() + 4f32),
+        galley,
+        Color32::WHITE,
+    );
+}
@@ -1,5 +1,11 @@
+pub mod checkerboard;
+pub mod color;
 pub mod crop;
+pub mod loupe;
 pub mod move_resize;
 pub mod shape;
+pub mod snap;
+pub mod tiled;
 pub mod utils;
+pub mod viewport;
 pub mod window;
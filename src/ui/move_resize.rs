@@ -1,14 +1,18 @@
 use eframe::egui::{
-    Color32, CornerRadius, CursorIcon, Key, Pos2, Rect, Response, Sense, Stroke, StrokeKind, Ui,
-    Vec2,
+    self, Color32, CornerRadius, CursorIcon, Key, Pos2, Rect, Response, Sense, Stroke, StrokeKind,
+    Ui, Vec2, emath::Rot2,
 };
 
 use crate::ui::{
-    utils::{from_ratio_pos, from_ratio_rect, to_ratio_pos, to_ratio_rect},
+    utils::{rotate_pos, round_rect, to_image_pos, to_image_rect, to_screen_pos, to_screen_rect},
     window::RenderInfo,
 };
 
-const DEFAULT_INTERACT_RANGE: f32 = 10f32;
+pub const DEFAULT_INTERACT_RANGE: f32 = 10f32;
+/// The distance between the top side and the rotation handle.
+const ROTATION_HANDLE_DISTANCE: f32 = 24f32;
+/// Shift snaps the rotation to multiples of 15°.
+const ROTATION_SNAP: f32 = std::f32::consts::PI / 12f32;
 
 #[derive(Debug, Default, Clone)]
 enum MoveResizeState {
@@ -44,6 +48,10 @@ pub enum ResizeMode {
 #[derive(Debug, Default, Clone)]
 pub struct MoveResize {
     state: MoveResizeState,
+    /// Keep the width to height ratio while resizing.
+    pub aspect_ratio: Option<f32>,
+    /// The rotation of the rect around its center, the handles and resizing follow it.
+    angle: f32,
 }
 
 impl MoveResize {
@@ -53,21 +61,58 @@ impl MoveResize {
             state: MoveResizeState::Resize {
                 fixed_pos: start_pos,
             },
+            aspect_ratio: None,
+            angle: 0f32,
         }
     }
 
+    /// Whether the rect is being moved or resized.
+    pub fn is_dragging(&self) -> bool {
+        !matches!(self.state, MoveResizeState::None)
+    }
+
     pub fn ui(&mut self, ui: &mut Ui, render_info: &RenderInfo, rect: &mut Rect) {
-        let render_range = from_ratio_rect(rect, &render_info.screenshot_rect);
+        self.angle = 0f32;
+        self.ui_handles(ui, render_info, rect);
+    }
+
+    /// Like `ui` for a rect rotated by `angle` around its center, with an extra handle to rotate it.
+    pub fn ui_rotated(
+        &mut self,
+        ui: &mut Ui,
+        render_info: &RenderInfo,
+        rect: &mut Rect,
+        angle: &mut f32,
+    ) {
+        self.angle = *angle;
+        self.ui_handles(ui, render_info, rect);
+        rotation_handle(ui, to_screen_rect(rect, &render_info.view), angle);
+    }
+
+    fn ui_handles(&mut self, ui: &mut Ui, render_info: &RenderInfo, rect: &mut Rect) {
+        let render_range = to_screen_rect(rect, &render_info.view);
+        let center = render_range.center();
+        let angle = self.angle;
+        let rotated = |pos: Pos2| rotate_pos(pos, center, angle);
 
         if let Some(offset) = key_arrow_to_offset(ui) {
-            *rect = to_ratio_rect(
-                &render_range.translate(offset),
-                &render_info.screenshot_rect,
-            );
+            *rect = rect.translate(offset);
         }
 
+        // only take the pointer inside of the rotated rect, unless it is already moving
+        let corners = rotated_corners(render_range, self.angle);
+        let movable = matches!(self.state, MoveResizeState::Move { .. })
+            || ui
+                .ctx()
+                .pointer_hover_pos()
+                .is_some_and(|pos| render_range.contains(rotate_pos(pos, center, -self.angle)));
+        let move_range = if movable {
+            Rect::from_points(&corners)
+        } else {
+            Rect::from_center_size(center, Vec2::ZERO)
+        };
         let resp = ui
-            .allocate_rect(render_range, Sense::drag())
+            .allocate_rect(move_range, Sense::drag())
             .on_hover_cursor(CursorIcon::Grab);
         self.handle_move(ui, &resp, render_info, rect);
 
@@ -93,54 +138,61 @@ impl MoveResize {
                 CursorIcon::ResizeSouthWest,
             ),
         ] {
-            let handle = add_control_point(ui, move_pos, cursor_icon, DEFAULT_INTERACT_RANGE);
-            self.handle_resize(ui, &handle, render_info, rect, ResizeMode::Fixed(fixed_pos));
+            let handle =
+                add_control_point(ui, rotated(move_pos), cursor_icon, DEFAULT_INTERACT_RANGE);
+            self.handle_resize(
+                ui,
+                &handle,
+                render_info,
+                rect,
+                ResizeMode::Fixed(rotated(fixed_pos)),
+            );
         }
 
         let handle = add_control_point(
             ui,
-            render_range.left_center(),
+            rotated(render_range.left_center()),
             CursorIcon::ResizeWest,
             DEFAULT_INTERACT_RANGE,
         );
         self.handle_resize_side(&handle, render_info, rect, || ResizeSideInfo {
-            fixed: render_range.right_top(),
+            fixed: rotated(render_range.right_top()),
             length: render_range.height(),
             is_x: true,
         });
 
         let handle = add_control_point(
             ui,
-            render_range.right_center(),
+            rotated(render_range.right_center()),
             CursorIcon::ResizeEast,
             DEFAULT_INTERACT_RANGE,
         );
         self.handle_resize_side(&handle, render_info, rect, || ResizeSideInfo {
-            fixed: render_range.left_top(),
+            fixed: rotated(render_range.left_top()),
             length: render_range.height(),
             is_x: true,
         });
 
         let handle = add_control_point(
             ui,
-            render_range.center_top(),
+            rotated(render_range.center_top()),
             CursorIcon::ResizeNorth,
             DEFAULT_INTERACT_RANGE,
         );
         self.handle_resize_side(&handle, render_info, rect, || ResizeSideInfo {
-            fixed: render_range.left_bottom(),
+            fixed: rotated(render_range.left_bottom()),
             length: render_range.width(),
             is_x: false,
         });
 
         let handle = add_control_point(
             ui,
-            render_range.center_bottom(),
+            rotated(render_range.center_bottom()),
             CursorIcon::ResizeSouth,
             DEFAULT_INTERACT_RANGE,
         );
         self.handle_resize_side(&handle, render_info, rect, || ResizeSideInfo {
-            fixed: render_range.left_top(),
+            fixed: rotated(render_range.left_top()),
             length: render_range.width(),
             is_x: false,
         });
@@ -174,21 +226,19 @@ impl MoveResize {
             if let Some(pos) = resp.interact_pointer_pos()
                 && let MoveResizeState::Resize { fixed_pos } = self.state
             {
-                let new_range = if ui.input(|i| i.modifiers.shift) {
-                    let offset = pos - fixed_pos;
-                    let offset_abs = offset.abs();
-                    let size = f32::min(offset_abs.x, offset_abs.y);
-                    Rect::from_two_pos(
-                        Pos2 {
-                            x: fixed_pos.x + size.copysign(offset.x),
-                            y: fixed_pos.y + size.copysign(offset.y),
-                        },
-                        fixed_pos,
-                    )
-                } else {
-                    Rect::from_two_pos(pos, fixed_pos)
-                };
-                *rect = to_ratio_rect(&new_range, &render_info.screenshot_rect);
+                let pos = render_info.snap_targets.snap_pos(resp, pos);
+                // shift keeps a square if no ratio is given
+                let aspect_ratio = self
+                    .aspect_ratio
+                    .or(ui.input(|i| i.modifiers.shift).then_some(1f32));
+                // resize in the frame rotated around the fixed point, which keeps it in place
+                let pos = rotate_pos(pos, fixed_pos, -self.angle);
+                let new_range = resize_corner(fixed_pos, pos, aspect_ratio);
+                let new_range = Rect::from_center_size(
+                    rotate_pos(new_range.center(), fixed_pos, self.angle),
+                    new_range.size(),
+                );
+                *rect = round_rect(to_image_rect(&new_range, &render_info.view));
             }
         }
         if resp.drag_stopped() {
@@ -209,7 +259,7 @@ impl MoveResize {
         {
             ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
             if resp.drag_started() {
-                let render_range = from_ratio_rect(rect, &render_info.screenshot_rect);
+                let render_range = to_screen_rect(rect, &render_info.view);
                 self.state = MoveResizeState::Move {
                     offset: current_pos - resp.drag_motion() - render_range.min,
                     size: render_range.size(),
@@ -218,10 +268,14 @@ impl MoveResize {
             if let MoveResizeState::Move { offset, size } = self.state {
                 let mut allowed_range = render_info.screenshot_rect;
                 allowed_range.max -= size;
-                let new_min = allowed_range.clamp(current_pos - offset);
+                // a rotated rect snaps with its visible bounds, like it is snapped to
+                let moved = Rect::from_min_size(current_pos - offset, size);
+                let bounds = Rect::from_points(&rotated_corners(moved, self.angle));
+                let snapped = render_info.snap_targets.snap_rect(resp, bounds);
+                let new_min = allowed_range.clamp(moved.min + (snapped.min - bounds.min));
                 let new_max = new_min + size;
                 let new_range = Rect::from_two_pos(new_min, new_max);
-                *rect = to_ratio_rect(&new_range, &render_info.screenshot_rect);
+                *rect = round_rect(to_image_rect(&new_range, &render_info.view));
             }
         }
         if resp.drag_stopped() {
@@ -249,19 +303,22 @@ impl MoveResize {
                 is_x,
             }) = self.state
             {
-                let other_pos = if is_x {
-                    Pos2 {
-                        x: current_pos.x,
-                        y: fixed.y + length,
-                    }
-                } else {
-                    Pos2 {
-                        x: fixed.x + length,
-                        y: current_pos.y,
-                    }
-                };
-                let new_range = Rect::from_two_pos(fixed, other_pos);
-                *rect = to_ratio_rect(&new_range, &render_info.screenshot_rect);
+                let current_pos = render_info.snap_targets.snap_pos(resp, current_pos);
+                let current_pos = rotate_pos(current_pos, fixed, -self.angle);
+                let new_range = resize_side(
+                    ResizeSideInfo {
+                        fixed,
+                        length,
+                        is_x,
+                    },
+                    current_pos,
+                    self.aspect_ratio,
+                );
+                let new_range = Rect::from_center_size(
+                    rotate_pos(new_range.center(), fixed, self.angle),
+                    new_range.size(),
+                );
+                *rect = round_rect(to_image_rect(&new_range, &render_info.view));
             }
         }
         if resp.drag_stopped() {
@@ -270,6 +327,60 @@ impl MoveResize {
     }
 }
 
+/// The rect spanned by dragging a corner to `pos` with the opposite corner at `fixed_pos`, it
+/// shrinks to `aspect_ratio` inside of the dragged rect if one is given.
+fn resize_corner(fixed_pos: Pos2, pos: Pos2, aspect_ratio: Option<f32>) -> Rect {
+    let Some(aspect_ratio) = aspect_ratio else {
+        return Rect::from_two_pos(pos, fixed_pos);
+    };
+    let offset = pos - fixed_pos;
+    let offset_abs = offset.abs();
+    let width = f32::min(offset_abs.x, offset_abs.y * aspect_ratio);
+    Rect::from_two_pos(
+        Pos2 {
+            x: fixed_pos.x + width.copysign(offset.x),
+            y: fixed_pos.y + (width / aspect_ratio).copysign(offset.y),
+        },
+        fixed_pos,
+    )
+}
+
+/// The rect spanned by dragging a side to `pos` with the opposite side described by `side`, the
+/// other sides grow around their center to keep `aspect_ratio` if one is given.
+fn resize_side(side: ResizeSideInfo, pos: Pos2, aspect_ratio: Option<f32>) -> Rect {
+    let ResizeSideInfo {
+        fixed,
+        length,
+        is_x,
+    } = side;
+    let other_pos = if is_x {
+        Pos2 {
+            x: pos.x,
+            y: fixed.y + length,
+        }
+    } else {
+        Pos2 {
+            x: fixed.x + length,
+            y: pos.y,
+        }
+    };
+    let mut new_range = Rect::from_two_pos(fixed, other_pos);
+    if let Some(aspect_ratio) = aspect_ratio {
+        if is_x {
+            let center = fixed.y + length / 2f32;
+            let half = new_range.width() / aspect_ratio / 2f32;
+            new_range.min.y = center - half;
+            new_range.max.y = center + half;
+        } else {
+            let center = fixed.x + length / 2f32;
+            let half = new_range.height() * aspect_ratio / 2f32;
+            new_range.min.x = center - half;
+            new_range.max.x = center + half;
+        }
+    }
+    new_range
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct LineMove {
     // state: LineMoveState,
@@ -278,30 +389,37 @@ pub struct LineMove {
 }
 
 impl LineMove {
+    /// `points` are extra control points (e.g. the bend point of a curve), each of them gets its
+    /// own handle and follows the line when the whole line is moved.
     pub fn ui(
         &mut self,
         ui: &mut Ui,
         render_info: &RenderInfo,
         pos_start: &mut Pos2,
         pos_end: &mut Pos2,
+        points: &mut [Pos2],
         expand_start: f32,
     ) {
-        let render_start_pos = from_ratio_pos(pos_start, &render_info.screenshot_rect);
-        let render_end_pos = from_ratio_pos(pos_end, &render_info.screenshot_rect);
+        let render_start_pos = to_screen_pos(pos_start, &render_info.view);
+        let render_end_pos = to_screen_pos(pos_end, &render_info.view);
 
         if let Some(offset) = key_arrow_to_offset(ui) {
-            *pos_start = to_ratio_pos(&(render_start_pos + offset), &render_info.screenshot_rect);
-            *pos_end = to_ratio_pos(&(render_end_pos + offset), &render_info.screenshot_rect);
+            *pos_start += offset;
+            *pos_end += offset;
+            translate_points(points, offset);
         }
 
         let mut render_range =
             Rect::from_center_size(render_start_pos, Vec2::splat(expand_start * 2f32));
         render_range.extend_with(render_end_pos);
+        for point in points.iter() {
+            render_range.extend_with(to_screen_pos(point, &render_info.view));
+        }
         let render_range = render_range.expand(2f32);
         let resp = ui
             .allocate_rect(render_range, Sense::drag())
             .on_hover_cursor(CursorIcon::Grab);
-        self.handle_move(ui, &resp, render_info, pos_start, pos_end);
+        self.handle_move(ui, &resp, render_info, pos_start, pos_end, points);
 
         let handle = add_control_point(
             ui,
@@ -314,6 +432,16 @@ impl LineMove {
         let handle =
             add_control_point(ui, render_end_pos, CursorIcon::Grab, DEFAULT_INTERACT_RANGE);
         self.handle_move_end(ui, &handle, render_info, pos_start, pos_end);
+
+        for point in points.iter_mut() {
+            let handle = add_control_point(
+                ui,
+                to_screen_pos(point, &render_info.view),
+                CursorIcon::Grab,
+                DEFAULT_INTERACT_RANGE,
+            );
+            self.handle_move_point(ui, &handle, render_info, point);
+        }
     }
 
     pub fn handle_move_start(
@@ -328,10 +456,12 @@ impl LineMove {
             && let Some(current_pos) = resp.interact_pointer_pos()
         {
             ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
-            *pos_start = to_ratio_pos(
+            let current_pos = render_info.snap_targets.snap_pos(resp, current_pos);
+            *pos_start = to_image_pos(
                 &calc_pos_with_shfit_modifier(ui, current_pos, pos_end, render_info),
-                &render_info.screenshot_rect,
-            );
+                &render_info.view,
+            )
+            .round();
         }
     }
 
@@ -347,10 +477,28 @@ impl LineMove {
             && let Some(current_pos) = resp.interact_pointer_pos()
         {
             ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
-            *pos_end = to_ratio_pos(
+            let current_pos = render_info.snap_targets.snap_pos(resp, current_pos);
+            *pos_end = to_image_pos(
                 &calc_pos_with_shfit_modifier(ui, current_pos, pos_start, render_info),
-                &render_info.screenshot_rect,
-            );
+                &render_info.view,
+            )
+            .round();
+        }
+    }
+
+    pub fn handle_move_point(
+        &mut self,
+        ui: &mut Ui,
+        resp: &Response,
+        render_info: &RenderInfo,
+        pos: &mut Pos2,
+    ) {
+        if resp.dragged()
+            && let Some(current_pos) = resp.interact_pointer_pos()
+        {
+            ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
+            let current_pos = render_info.snap_targets.snap_pos(resp, current_pos);
+            *pos = to_image_pos(&current_pos, &render_info.view).round();
         }
     }
 
@@ -362,33 +510,44 @@ impl LineMove {
         render_info: &RenderInfo,
         pos_start: &mut Pos2,
         pos_end: &mut Pos2,
+        points: &mut [Pos2],
     ) {
         if resp.dragged()
             && let Some(current_pos) = resp.interact_pointer_pos()
         {
-            let render_start_pos = from_ratio_pos(pos_start, &render_info.screenshot_rect);
-            let render_end_pos = from_ratio_pos(pos_end, &render_info.screenshot_rect);
+            let render_start_pos = to_screen_pos(pos_start, &render_info.view);
+            let render_end_pos = to_screen_pos(pos_end, &render_info.view);
             if resp.drag_started() {
                 self.line = render_end_pos - render_start_pos;
                 self.offset = current_pos - resp.drag_motion() - render_start_pos;
             }
             ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
-            let start = current_pos - self.offset;
+            let start = render_info
+                .snap_targets
+                .snap_pos(resp, current_pos - self.offset);
             let end = start + self.line;
-            *pos_start = to_ratio_pos(&start, &render_info.screenshot_rect);
-            *pos_end = to_ratio_pos(&end, &render_info.screenshot_rect);
+            let start = to_image_pos(&start, &render_info.view).round();
+            translate_points(points, start - *pos_start);
+            *pos_start = start;
+            *pos_end = to_image_pos(&end, &render_info.view).round();
         }
     }
 }
 
-fn calc_pos_with_shfit_modifier(
+fn translate_points(points: &mut [Pos2], offset: Vec2) {
+    for point in points {
+        *point += offset;
+    }
+}
+
+pub fn calc_pos_with_shfit_modifier(
     ui: &mut Ui,
     pos: Pos2,
-    ratio_base: &Pos2,
+    image_base: &Pos2,
     render_info: &RenderInfo,
 ) -> Pos2 {
     if ui.input(|i| i.modifiers.shift) {
-        let base = from_ratio_pos(ratio_base, &render_info.screenshot_rect);
+        let base = to_screen_pos(image_base, &render_info.view);
         let offset = (pos - base).abs();
         if offset.x < offset.y {
             Pos2 {
@@ -406,7 +565,12 @@ fn calc_pos_with_shfit_modifier(
     }
 }
 
-fn add_control_point(ui: &mut Ui, pos: Pos2, icon: CursorIcon, interact_range: f32) -> Response {
+pub fn add_control_point(
+    ui: &mut Ui,
+    pos: Pos2,
+    icon: CursorIcon,
+    interact_range: f32,
+) -> Response {
     const FILL_COLOR: Color32 = Color32::from_gray(0xee);
     const RADIUS: f32 = 6f32;
     const HOVER_RADIUS: f32 = 10f32;
@@ -434,11 +598,12 @@ fn add_control_point(ui: &mut Ui, pos: Pos2, icon: CursorIcon, interact_range: f
     resp
 }
 
+/// The offset in image pixels the arrow keys nudge the active shape by.
 pub fn key_arrow_to_offset(ui: &Ui) -> Option<Vec2> {
     ui.input(|i| {
         let mut res = Vec2::ZERO;
         let mut key_pressed = false;
-        const MOVE_UNIT: f32 = 3f32;
+        const MOVE_UNIT: f32 = 1f32;
 
         if i.key_pressed(Key::ArrowDown) {
             res.y += MOVE_UNIT;
@@ -460,6 +625,66 @@ pub fn key_arrow_to_offset(ui: &Ui) -> Option<Vec2> {
     })
 }
 
+/// The corners of `rect` rotated by `angle` around its center, clockwise from the left top.
+pub fn rotated_corners(rect: Rect, angle: f32) -> [Pos2; 4] {
+    [
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+    ]
+    .map(|pos| rotate_pos(pos, rect.center(), angle))
+}
+
+/// A handle above the top side of `rect` rotated by `angle`, dragging it rotates the rect around
+/// its center. Shift snaps the angle to multiples of 15°.
+pub fn rotation_handle(ui: &mut Ui, rect: Rect, angle: &mut f32) {
+    let center = rect.center();
+    let top = rotate_pos(rect.center_top(), center, *angle);
+    let handle_pos = top + Rot2::from_angle(*angle) * Vec2::new(0f32, -ROTATION_HANDLE_DISTANCE);
+    ui.painter().line_segment(
+        [top, handle_pos],
+        Stroke::new(1f32, Color32::from_gray(0xee)),
+    );
+    let handle = add_control_point(ui, handle_pos, CursorIcon::Grab, DEFAULT_INTERACT_RANGE);
+    if handle.dragged()
+        && let Some(current_pos) = handle.interact_pointer_pos()
+    {
+        ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
+        let mut new_angle = (current_pos - center).angle() + std::f32::consts::FRAC_PI_2;
+        if ui.input(|i| i.modifiers.shift) {
+            new_angle = (new_angle / ROTATION_SNAP).round() * ROTATION_SNAP;
+        }
+        *angle = new_angle.rem_euclid(std::f32::consts::TAU);
+    }
+}
+
+/// Like `hover_range` for a range rotated by `angle` around its center.
+pub fn hover_rotated_range(ui: &mut Ui, range: Rect, angle: f32, shot_mode: bool) -> bool {
+    if angle == 0f32 {
+        return hover_range(ui, range, shot_mode);
+    }
+    let corners = rotated_corners(range, angle);
+    let hovered = ui
+        .ctx()
+        .pointer_hover_pos()
+        .is_some_and(|pos| range.contains(rotate_pos(pos, range.center(), -angle)));
+    // the corners of the bounding rect must not cover the shapes below
+    let hover_rect = if hovered {
+        Rect::from_points(&corners)
+    } else {
+        Rect::from_center_size(range.center(), Vec2::ZERO)
+    };
+    let response = ui.allocate_rect(hover_rect, Sense::click());
+    if !shot_mode && response.hovered() {
+        ui.painter().add(egui::Shape::closed_line(
+            corners.to_vec(),
+            Stroke::new(1f32, Color32::GRAY),
+        ));
+    }
+    response.clicked()
+}
+
 pub fn hover_range(ui: &mut Ui, range: Rect, shot_mode: bool) -> bool {
     let response = ui.allocate_rect(range, Sense::click());
     if !shot_mode && response.hovered() {
@@ -472,3 +697,99 @@ pub fn hover_range(ui: &mut Ui, range: Rect, shot_mode: bool) -> bool {
     }
     response.clicked()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_ratio(rect: Rect, aspect_ratio: f32) {
+        assert!(
+            (rect.aspect_ratio() - aspect_ratio).abs() < 1e-4,
+            "{rect:?} is not {aspect_ratio}"
+        );
+    }
+
+    #[test]
+    fn corner_keeps_the_ratio() {
+        let fixed = Pos2::new(100f32, 100f32);
+        for aspect_ratio in [1f32, 4f32 / 3f32, 16f32 / 9f32, 0.5f32] {
+            // every direction away from the fixed corner, wider and taller than the ratio
+            for offset in [
+                Vec2::new(120f32, 40f32),
+                Vec2::new(40f32, 120f32),
+                Vec2::new(-120f32, 40f32),
+                Vec2::new(40f32, -120f32),
+                Vec2::new(-80f32, -90f32),
+            ] {
+                let rect = resize_corner(fixed, fixed + offset, Some(aspect_ratio));
+                assert_ratio(rect, aspect_ratio);
+                // the fixed corner stays, and the rect stays inside of the dragged one
+                assert!(
+                    [
+                        rect.left_top(),
+                        rect.right_top(),
+                        rect.left_bottom(),
+                        rect.right_bottom()
+                    ]
+                    .contains(&fixed)
+                );
+                assert!(Rect::from_two_pos(fixed, fixed + offset).contains_rect(rect));
+                // one side follows the pointer
+                assert!(
+                    rect.width() == offset.x.abs() || rect.height() == offset.y.abs(),
+                    "{rect:?} {offset:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn corner_is_free_without_a_ratio() {
+        let fixed = Pos2::new(10f32, 20f32);
+        let pos = Pos2::new(-30f32, 70f32);
+        assert_eq!(
+            resize_corner(fixed, pos, None),
+            Rect::from_two_pos(fixed, pos)
+        );
+    }
+
+    #[test]
+    fn side_keeps_the_ratio() {
+        // the left side is fixed and the right side is dragged
+        let left = ResizeSideInfo {
+            fixed: Pos2::new(0f32, 0f32),
+            length: 90f32,
+            is_x: true,
+        };
+        let rect = resize_side(left.clone(), Pos2::new(320f32, 500f32), Some(16f32 / 9f32));
+        assert_eq!(
+            rect,
+            Rect::from_min_max(Pos2::new(0f32, -45f32), Pos2::new(320f32, 135f32))
+        );
+        assert_ratio(rect, 16f32 / 9f32);
+
+        // the top side is fixed and the bottom side is dragged above it
+        let top = ResizeSideInfo {
+            fixed: Pos2::new(0f32, 0f32),
+            length: 100f32,
+            is_x: false,
+        };
+        let rect = resize_side(top.clone(), Pos2::new(-500f32, -50f32), Some(1f32));
+        assert_eq!(
+            rect,
+            Rect::from_min_max(Pos2::new(25f32, -50f32), Pos2::new(75f32, 0f32))
+        );
+
+        // without a ratio only the dragged side moves
+        let rect = resize_side(left, Pos2::new(320f32, 500f32), None);
+        assert_eq!(
+            rect,
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(320f32, 90f32))
+        );
+        let rect = resize_side(top, Pos2::new(-500f32, 40f32), None);
+        assert_eq!(
+            rect,
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(100f32, 40f32))
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use eframe::egui::{
+    self, CursorIcon, FontFamily, FontId, FontSelection, Label, Pos2, Rect, Response, Rgba, Slider,
+    Stroke, TextEdit, Ui, Vec2, Widget,
+};
+
+use crate::ui::{
+    color::color_edit,
+    move_resize::{DEFAULT_INTERACT_RANGE, MoveResize, ResizeMode, add_control_point, hover_range},
+    shape::{
+        CreateAt, Shape,
+        shadow::DropShadow,
+        stroke::{StrokeStyle, rounded_rect_points},
+    },
+    utils::{to_image_pos, to_image_rect, to_screen_pos, to_screen_rect},
+    window::RenderInfo,
+};
+
+#[derive(Clone)]
+pub struct CalloutAttribute {
+    pub line_width: f32,
+    pub fill_color: Rgba,
+    pub border_color: Rgba,
+    pub radius: f32,
+    pub text_color: Rgba,
+    pub text: String,
+    pub font_size: f32,
+    pub stroke_style: StrokeStyle,
+    pub shadow: DropShadow,
+}
+
+impl Default for CalloutAttribute {
+    fn default() -> Self {
+        Self {
+            line_width: 3f32,
+            fill_color: Rgba::WHITE,
+            border_color: Rgba::RED,
+            radius: 0.2f32,
+            text_color: Rgba::BLACK,
+            text: "Edit Text here".to_string(),
+            font_size: 20f32,
+            stroke_style: Default::default(),
+            shadow: Default::default(),
+        }
+    }
+}
+
+impl CalloutAttribute {
+    pub fn ui(&mut self, ui: &mut Ui, font_family: FontFamily) {
+        Label::new("Line width").selectable(false).ui(ui);
+        Slider::new(&mut self.line_width, 1f32..=20f32).ui(ui);
+        ui.end_row();
+
+        self.stroke_style.ui(ui);
+
+        Label::new("Fill Color").selectable(false).ui(ui);
+        color_edit(ui, "callout_fill_color", &mut self.fill_color);
+        ui.end_row();
+
+        Label::new("Border Color").selectable(false).ui(ui);
+        color_edit(ui, "callout_border_color", &mut self.border_color);
+        ui.end_row();
+
+        Label::new("Radius").selectable(false).ui(ui);
+        Slider::new(&mut self.radius, 0f32..=1f32).ui(ui);
+        ui.end_row();
+
+        Label::new("Text Color").selectable(false).ui(ui);
+        color_edit(ui, "callout_text_color", &mut self.text_color);
+        ui.end_row();
+
+        Label::new("Text").selectable(false).ui(ui);
+        TextEdit::multiline(&mut self.text)
+            .font(FontSelection::FontId(FontId::new(12f32, font_family)))
+            .ui(ui);
+        ui.end_row();
+
+        Label::new("Font Size").selectable(false).ui(ui);
+        Slider::new(&mut self.font_size, 0f32..=200f32).ui(ui);
+        ui.end_row();
+
+        self.shadow.ui(ui);
+    }
+}
+
+#[derive(Clone)]
+pub struct Callout {
+    pub range: Rect,
+    /// The point the tail points to.
+    pub tail_pos: Pos2,
+
+    pub attributes: CalloutAttribute,
+
+    move_resize: MoveResize,
+}
+
+impl CreateAt for Callout {
+    type Attr = CalloutAttribute;
+    fn create_at(
+        pos: Pos2,
+        mut attributes: CalloutAttribute,
+        render_info: &RenderInfo,
+    ) -> Box<dyn Shape> {
+        if attributes.text.trim().is_empty() {
+            attributes.text = "Edit Text here".to_string();
+        }
+        let range = Rect::from_min_max(pos, pos + Vec2::splat(30f32));
+        Box::new(Callout {
+            range: to_image_rect(&range, &render_info.view),
+            tail_pos: to_image_pos(&default_tail_pos(range), &render_info.view),
+            attributes,
+            move_resize: MoveResize::resize(pos),
+        })
+    }
+}
+
+impl Shape for Callout {
+    fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
+        let render_range = to_screen_rect(&self.range, &render_info.view);
+        let render_tail_pos = to_screen_pos(&self.tail_pos, &render_info.view);
+        let radius =
+            self.attributes.radius * render_range.width().min(render_range.height()) / 2f32;
+        let stroke = Stroke::new(
+            self.attributes.line_width * render_info.pixel_ratio,
+            self.attributes.border_color,
+        );
+
+        let tail = tail_base(render_range, radius, render_tail_pos);
+        let mut shapes = vec![egui::Shape::convex_polygon(
+            rounded_rect_points(render_range, radius),
+            self.attributes.fill_color,
+            Stroke::NONE,
+        )];
+        if let Some((base1, base2)) = tail {
+            // the tail is convex on its own, the outline is not
+            shapes.push(egui::Shape::convex_polygon(
+                vec![base1, render_tail_pos, base2],
+                self.attributes.fill_color,
+                Stroke::NONE,
+            ));
+        }
+        self.attributes.stroke_style.paint(
+            &mut shapes,
+            outline_points(render_range, radius, tail.map(|t| (t, render_tail_pos))),
+            true,
+            stroke,
+            render_info.pixel_ratio,
+        );
+
+        // wrap the text to the inner width of the box
+        let padding = stroke.width + 4f32 * render_info.pixel_ratio;
+        let galley = ui.painter().layout(
+            self.attributes.text.clone(),
+            FontId::new(
+                self.attributes.font_size * render_info.pixel_ratio,
+                render_info.user_font.clone(),
+            ),
+            self.attributes.text_color.into(),
+            (render_range.width() - padding * 2f32).max(0f32),
+        );
+        shapes.push(egui::Shape::galley(
+            render_range.min + Vec2::splat(padding),
+            galley,
+            self.attributes.text_color.into(),
+        ));
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
+
+        if is_active {
+            self.move_resize.ui(ui, render_info, &mut self.range);
+
+            let handle = add_control_point(
+                ui,
+                render_tail_pos,
+                CursorIcon::Grab,
+                DEFAULT_INTERACT_RANGE,
+            );
+            if handle.dragged()
+                && let Some(current_pos) = handle.interact_pointer_pos()
+            {
+                ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
+                self.tail_pos = to_image_pos(&current_pos, &render_info.view).round();
+            }
+            true
+        } else {
+            let mut hover = render_range.expand(stroke.width / 2f32 + 2f32);
+            hover.extend_with(render_tail_pos);
+            hover_range(ui, hover, render_info.shot_mode)
+        }
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui, render_info: &RenderInfo) {
+        self.attributes.ui(ui, render_info.user_font.clone());
+    }
+
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        to_screen_rect(&self.range, &render_info.view)
+    }
+
+    fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
+        self.move_resize
+            .handle_resize(ui, resp, render_info, &mut self.range, ResizeMode::None);
+        // keep the tail below the box while it is being created
+        self.tail_pos = to_image_pos(
+            &default_tail_pos(to_screen_rect(&self.range, &render_info.view)),
+            &render_info.view,
+        );
+    }
+}
+
+fn default_tail_pos(range: Rect) -> Pos2 {
+    Pos2 {
+        x: range.left() + range.width() / 4f32,
+        y: range.bottom() + 30f32,
+    }
+}
+
+/// Find where the tail joins the box. Return the two base points in clockwise order, or `None` if
+/// the tail point is inside the box.
+fn tail_base(range: Rect, radius: f32, tail_pos: Pos2) -> Option<(Pos2, Pos2)> {
+    if range.contains(tail_pos) {
+        return None;
+    }
+    let offset = tail_pos - range.center();
+    let side = if offset.x.abs() * range.height() > offset.y.abs() * range.width() {
+        if offset.x > 0f32 { 1 } else { 3 }
+    } else if offset.y > 0f32 {
+        2
+    } else {
+        0
+    };
+    let (start, end) = match side {
+        0 => (range.left_top(), range.right_top()),
+        1 => (range.right_top(), range.right_bottom()),
+        2 => (range.right_bottom(), range.left_bottom()),
+        _ => (range.left_bottom(), range.left_top()),
+    };
+    let length = (end - start).length();
+    let dir = (end - start) / length;
+    let half_width = (range.width().min(range.height()) * 0.2f32).min(20f32);
+
+    // keep the base on the straight part of the side
+    let min = radius + half_width;
+    let max = length - radius - half_width;
+    let center = if min < max {
+        (tail_pos - start).dot(dir).clamp(min, max)
+    } else {
+        length / 2f32
+    };
+    Some((
+        start + dir * (center - half_width),
+        start + dir * (center + half_width),
+    ))
+}
+
+/// The outline of the rounded box in clockwise order, with the tail inserted into the side its
+/// base lies on.
+fn outline_points(range: Rect, radius: f32, tail: Option<((Pos2, Pos2), Pos2)>) -> Vec<Pos2> {
+    let mut points = rounded_rect_points(range, radius);
+    if let Some(((base1, base2), tail_pos)) = tail {
+        let base = base1.lerp(base2, 0.5f32);
+        let distance = |i: usize| {
+            let (start, end) = (points[i], points[(i + 1) % points.len()]);
+            let t = ((base - start).dot(end - start) / (end - start).length_sq().max(f32::EPSILON))
+                .clamp(0f32, 1f32);
+            base.distance(start.lerp(end, t))
+        };
+        let edge = (0..points.len())
+            .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+            .unwrap_or(0);
+        points.splice(edge + 1..edge + 1, [base1, tail_pos, base2]);
+    }
+    points
+}
@@ -1,15 +1,14 @@
-use eframe::{
-    egui::{
-        Label, Pos2, Rect, Response, Rgba, Slider, Stroke, Ui, Vec2, Widget,
-        color_picker::{Alpha, color_edit_button_rgba},
-    },
-    epaint::EllipseShape,
-};
+use eframe::egui::{self, Label, Pos2, Rect, Response, Rgba, Slider, Stroke, Ui, Vec2, Widget};
 
 use crate::ui::{
-    move_resize::{MoveResize, ResizeMode, hover_range},
-    shape::{CreateAt, Shape},
-    utils::{from_ratio_rect, to_ratio_rect},
+    color::color_edit,
+    move_resize::{MoveResize, ResizeMode, hover_rotated_range, rotated_corners},
+    shape::{
+        CreateAt, Shape,
+        shadow::DropShadow,
+        stroke::{StrokeStyle, ellipse_points},
+    },
+    utils::{rotate_pos, to_image_rect, to_screen_rect},
     window::RenderInfo,
 };
 
@@ -18,6 +17,8 @@ pub struct CircleAttribute {
     pub line_width: f32,
     pub fill_color: Rgba,
     pub border_color: Rgba,
+    pub stroke_style: StrokeStyle,
+    pub shadow: DropShadow,
 }
 
 impl Default for CircleAttribute {
@@ -26,6 +27,8 @@ impl Default for CircleAttribute {
             line_width: 3f32,
             border_color: Rgba::RED,
             fill_color: Rgba::TRANSPARENT,
+            stroke_style: Default::default(),
+            shadow: Default::default(),
         }
     }
 }
@@ -36,19 +39,25 @@ impl CircleAttribute {
         Slider::new(&mut self.line_width, 1f32..=20f32).ui(ui);
         ui.end_row();
 
+        self.stroke_style.ui(ui);
+
         Label::new("Fill Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.fill_color, Alpha::OnlyBlend);
+        color_edit(ui, "circle_fill_color", &mut self.fill_color);
         ui.end_row();
 
         Label::new("Border Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.border_color, Alpha::OnlyBlend);
+        color_edit(ui, "circle_border_color", &mut self.border_color);
         ui.end_row();
+
+        self.shadow.ui(ui);
     }
 }
 
 #[derive(Clone)]
 pub struct Circle {
     pub range: Rect,
+    /// The rotation around the center in radians, clockwise.
+    pub angle: f32,
 
     pub attributes: CircleAttribute,
 
@@ -59,10 +68,11 @@ impl CreateAt for Circle {
     type Attr = CircleAttribute;
     fn create_at(pos: Pos2, attr: CircleAttribute, render_info: &RenderInfo) -> Box<dyn Shape> {
         Box::new(Circle {
-            range: to_ratio_rect(
+            range: to_image_rect(
                 &Rect::from_min_max(pos, pos + Vec2::splat(30f32)),
-                &render_info.screenshot_rect,
+                &render_info.view,
             ),
+            angle: 0f32,
             attributes: attr,
             move_resize: MoveResize::resize(pos),
         })
@@ -71,23 +81,38 @@ impl CreateAt for Circle {
 
 impl Shape for Circle {
     fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
-        let render_range = from_ratio_rect(&self.range, &render_info.screenshot_rect);
-        ui.painter().add(EllipseShape {
-            center: render_range.center(),
-            radius: render_range.size() / 2f32,
-            fill: self.attributes.fill_color.into(),
-            stroke: Stroke::new(
+        let render_range = to_screen_rect(&self.range, &render_info.view);
+        let points: Vec<Pos2> = ellipse_points(render_range.center(), render_range.size() / 2f32)
+            .into_iter()
+            .map(|pos| rotate_pos(pos, render_range.center(), self.angle))
+            .collect();
+        let mut shapes = vec![egui::Shape::convex_polygon(
+            points.clone(),
+            self.attributes.fill_color,
+            Stroke::NONE,
+        )];
+        self.attributes.stroke_style.paint(
+            &mut shapes,
+            points,
+            true,
+            Stroke::new(
                 self.attributes.line_width * render_info.pixel_ratio,
                 self.attributes.border_color,
             ),
-        });
+            render_info.pixel_ratio,
+        );
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
         if is_active {
-            self.move_resize.ui(ui, render_info, &mut self.range);
+            self.move_resize
+                .ui_rotated(ui, render_info, &mut self.range, &mut self.angle);
             true
         } else {
-            hover_range(
+            hover_rotated_range(
                 ui,
                 render_range.expand(self.attributes.line_width / 2f32 + 2f32),
+                self.angle,
                 render_info.shot_mode,
             )
         }
@@ -97,6 +122,11 @@ impl Shape for Circle {
         self.attributes.ui(ui);
     }
 
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        let render_range = to_screen_rect(&self.range, &render_info.view);
+        Rect::from_points(&rotated_corners(render_range, self.angle))
+    }
+
     fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
         self.move_resize
             .handle_resize(ui, resp, render_info, &mut self.range, ResizeMode::None);
@@ -1,24 +1,153 @@
 use eframe::egui::{
-    Checkbox, Label, Pos2, Rect, Response, Rgba, Slider, Stroke, Ui, Vec2, Widget,
-    color_picker::{Alpha, color_edit_button_rgba},
-    emath::Rot2,
+    self, Color32, ComboBox, Key, Label, Pos2, Rect, Response, Rgba, Slider, Stroke, Ui, Vec2,
+    Widget, emath::Rot2,
 };
-use eframe::epaint::PathShape;
+use eframe::epaint::{PathShape, QuadraticBezierShape};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
 
 use crate::ui::{
-    move_resize::{LineMove, hover_range},
-    shape::{CreateAt, Shape},
-    utils::{from_ratio_pos, to_ratio_pos},
+    color::color_edit,
+    move_resize::{LineMove, calc_pos_with_shfit_modifier, hover_range},
+    shape::{CreateAt, Shape, shadow::DropShadow, stroke::StrokeStyle},
+    utils::{to_image_pos, to_screen_pos},
     window::RenderInfo,
 };
 
+/// clicking this close to the last vertex finishes a polyline
+const FINISH_RANGE: f32 = 10f32;
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, IntoStaticStr)]
+pub enum LineMode {
+    #[default]
+    Straight,
+    Curve,
+    Polyline,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, IntoStaticStr)]
+pub enum ArrowHead {
+    #[default]
+    None,
+    Open,
+    Triangle,
+    Barbed,
+    Dot,
+    Square,
+    Bar,
+    Diamond,
+}
+
+impl ArrowHead {
+    fn ui(&mut self, ui: &mut Ui, id_salt: &str) {
+        ComboBox::from_id_salt(id_salt)
+            .selected_text(<&'static str>::from(*self))
+            .show_ui(ui, |ui| {
+                for head in ArrowHead::iter() {
+                    ui.selectable_value(self, head, <&'static str>::from(head));
+                }
+            });
+    }
+
+    /// Paint the head with its tip at `tip_pos`, pointing away from `from_pos`, into `shapes` and
+    /// return the range covered by it.
+    fn paint(
+        self,
+        shapes: &mut Vec<egui::Shape>,
+        from_pos: Pos2,
+        tip_pos: Pos2,
+        length: f32,
+        stroke: Stroke,
+    ) -> Rect {
+        let dir = (tip_pos - from_pos).normalized();
+        let normal = dir.rot90();
+        let rot = Rot2::from_angle(std::f32::consts::TAU / 15.0);
+        let barb1 = tip_pos - length * (rot.inverse() * dir);
+        let barb2 = tip_pos - length * (rot * dir);
+
+        let points = match self {
+            ArrowHead::None => return Rect::NOTHING,
+            ArrowHead::Open => {
+                let points = vec![barb1, tip_pos, barb2];
+                shapes.push(egui::Shape::line(points.clone(), stroke));
+                points
+            }
+            ArrowHead::Triangle => {
+                let points = vec![barb1, tip_pos, barb2];
+                shapes.push(egui::Shape::convex_polygon(
+                    points.clone(),
+                    stroke.color,
+                    stroke,
+                ));
+                points
+            }
+            ArrowHead::Barbed => {
+                let points = vec![barb1, tip_pos, barb2, tip_pos - length * 0.7f32 * dir];
+                shapes.push(egui::Shape::Path(PathShape {
+                    points: points.clone(),
+                    closed: true,
+                    fill: stroke.color,
+                    stroke: stroke.into(),
+                }));
+                points
+            }
+            ArrowHead::Dot => {
+                let radius = length * 0.35f32;
+                shapes.push(egui::Shape::circle_filled(tip_pos, radius, stroke.color));
+                return Rect::from_center_size(tip_pos, Vec2::splat(radius * 2f32));
+            }
+            ArrowHead::Square => {
+                let half = length * 0.3f32;
+                let points = vec![
+                    tip_pos + half * (dir + normal),
+                    tip_pos + half * (dir - normal),
+                    tip_pos - half * (dir + normal),
+                    tip_pos - half * (dir - normal),
+                ];
+                shapes.push(egui::Shape::convex_polygon(
+                    points.clone(),
+                    stroke.color,
+                    Stroke::NONE,
+                ));
+                points
+            }
+            ArrowHead::Bar => {
+                let points = vec![
+                    tip_pos + length * 0.5f32 * normal,
+                    tip_pos - length * 0.5f32 * normal,
+                ];
+                shapes.push(egui::Shape::line_segment([points[0], points[1]], stroke));
+                points
+            }
+            ArrowHead::Diamond => {
+                let middle = tip_pos - length * 0.5f32 * dir;
+                let points = vec![
+                    tip_pos,
+                    middle + length * 0.3f32 * normal,
+                    tip_pos - length * dir,
+                    middle - length * 0.3f32 * normal,
+                ];
+                shapes.push(egui::Shape::convex_polygon(
+                    points.clone(),
+                    stroke.color,
+                    Stroke::NONE,
+                ));
+                points
+            }
+        };
+        Rect::from_points(&points).expand(stroke.width / 2f32)
+    }
+}
+
 #[derive(Clone)]
 pub struct LineAttribute {
     pub line_width: f32,
     pub line_color: Rgba,
-    pub arrow_start: bool,
-    pub arrow_end: bool,
+    pub arrow_start: ArrowHead,
+    pub arrow_end: ArrowHead,
     pub arrow_size: f32,
+    pub mode: LineMode,
+    pub stroke_style: StrokeStyle,
+    pub shadow: DropShadow,
 }
 
 impl Default for LineAttribute {
@@ -26,34 +155,54 @@ impl Default for LineAttribute {
         Self {
             line_width: 3f32,
             line_color: Rgba::RED,
-            arrow_start: false,
-            arrow_end: false,
-            arrow_size: 15f32,
+            arrow_start: ArrowHead::None,
+            arrow_end: ArrowHead::None,
+            arrow_size: 9f32,
+            mode: LineMode::Straight,
+            stroke_style: Default::default(),
+            shadow: Default::default(),
         }
     }
 }
 
 impl LineAttribute {
     pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Mode").selectable(false).ui(ui);
+        ui.horizontal(|ui| {
+            for mode in LineMode::iter() {
+                ui.selectable_value(&mut self.mode, mode, <&'static str>::from(mode));
+            }
+        });
+        ui.end_row();
+
         Label::new("Line width").selectable(false).ui(ui);
         Slider::new(&mut self.line_width, 1f32..=20f32).ui(ui);
         ui.end_row();
 
+        self.stroke_style.ui(ui);
+
         Label::new("Line Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.line_color, Alpha::OnlyBlend);
+        color_edit(ui, "line_color", &mut self.line_color);
         ui.end_row();
 
         Label::new("Arrow at start").selectable(false).ui(ui);
-        Checkbox::new(&mut self.arrow_start, "Arrow at start").ui(ui);
+        self.arrow_start.ui(ui, "arrow_start");
         ui.end_row();
 
         Label::new("Arrow at end").selectable(false).ui(ui);
-        Checkbox::new(&mut self.arrow_end, "Arrow at end").ui(ui);
+        self.arrow_end.ui(ui, "arrow_end");
         ui.end_row();
 
         Label::new("Arrow size").selectable(false).ui(ui);
         Slider::new(&mut self.arrow_size, 0f32..=50f32).ui(ui);
         ui.end_row();
+
+        self.shadow.ui(ui);
+    }
+
+    /// The head grows with the line width, so it always covers the end of a thick line.
+    fn head_length(&self) -> f32 {
+        self.arrow_size + self.line_width * 2f32
     }
 }
 
@@ -61,76 +210,195 @@ impl LineAttribute {
 pub struct Line {
     pub start_pos: Pos2,
     pub end_pos: Pos2,
+    /// The point the curve passes through halfway, `None` means the curve is not bent yet.
+    pub bend_pos: Option<Pos2>,
+    /// The vertices between start and end in polyline mode.
+    pub vertices: Vec<Pos2>,
 
     pub attributes: LineAttribute,
 
     line_move: LineMove,
+    /// A polyline is being drawn, clicks add vertices.
+    drawing: bool,
 }
 
 impl CreateAt for Line {
     type Attr = LineAttribute;
     fn create_at(pos: Pos2, attributes: LineAttribute, render_info: &RenderInfo) -> Box<dyn Shape> {
+        let drawing = attributes.mode == LineMode::Polyline;
+        let end_pos = if drawing {
+            pos
+        } else {
+            pos + Vec2 { x: 30f32, y: 0f32 }
+        };
         Box::new(Line {
-            start_pos: to_ratio_pos(&pos, &render_info.screenshot_rect),
-            end_pos: to_ratio_pos(
-                &(pos + Vec2 { x: 30f32, y: 0f32 }),
-                &render_info.screenshot_rect,
-            ),
+            start_pos: to_image_pos(&pos, &render_info.view),
+            end_pos: to_image_pos(&end_pos, &render_info.view),
+            bend_pos: None,
+            vertices: Vec::new(),
             attributes,
             line_move: Default::default(),
+            drawing,
         })
     }
 }
 
+impl Line {
+    /// The points which define the path in render coordinate. For a curve they are the control
+    /// points of the quadratic Bézier curve.
+    fn render_path(&self, render_info: &RenderInfo) -> Vec<Pos2> {
+        let render_start_pos = to_screen_pos(&self.start_pos, &render_info.view);
+        let render_end_pos = to_screen_pos(&self.end_pos, &render_info.view);
+        match self.attributes.mode {
+            LineMode::Straight => vec![render_start_pos, render_end_pos],
+            LineMode::Curve => {
+                let Some(bend_pos) = self.bend_pos else {
+                    return vec![render_start_pos, render_end_pos];
+                };
+                // the curve passes through the bend point at t = 0.5
+                let render_bend_pos = to_screen_pos(&bend_pos, &render_info.view);
+                let control_pos = render_bend_pos
+                    + (render_bend_pos - render_start_pos.lerp(render_end_pos, 0.5f32));
+                vec![render_start_pos, control_pos, render_end_pos]
+            }
+            LineMode::Polyline => std::iter::once(render_start_pos)
+                .chain(
+                    self.vertices
+                        .iter()
+                        .map(|p| to_screen_pos(p, &render_info.view)),
+                )
+                .chain(std::iter::once(render_end_pos))
+                .collect(),
+        }
+    }
+
+    fn finish_drawing(&mut self) {
+        self.drawing = false;
+        // the end point follows the cursor while drawing, drop it
+        if let Some(last) = self.vertices.pop() {
+            self.end_pos = last;
+        }
+    }
+
+    fn bend_ui(&mut self, ui: &mut Ui, render_info: &RenderInfo) {
+        let (start_pos, end_pos) = (self.start_pos, self.end_pos);
+        let mut bend_pos = [self
+            .bend_pos
+            .unwrap_or_else(|| start_pos.lerp(end_pos, 0.5f32))];
+        self.line_move.ui(
+            ui,
+            render_info,
+            &mut self.start_pos,
+            &mut self.end_pos,
+            &mut bend_pos,
+            0f32,
+        );
+        // keep an unbent curve straight while its ends are moved
+        let only_bend_moved = self.start_pos == start_pos && self.end_pos == end_pos;
+        if self.bend_pos.is_some()
+            || (only_bend_moved && bend_pos[0] != start_pos.lerp(end_pos, 0.5f32))
+        {
+            self.bend_pos = Some(bend_pos[0]);
+        }
+    }
+}
+
 impl Shape for Line {
     fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
-        let render_start_pos = from_ratio_pos(&self.start_pos, &render_info.screenshot_rect);
-        let render_end_pos = from_ratio_pos(&self.end_pos, &render_info.screenshot_rect);
+        if self.drawing && !is_active {
+            self.finish_drawing();
+        }
+        if self.drawing
+            && let Some(pos) = ui.input(|i| i.pointer.hover_pos())
+        {
+            let last_pos = *self.vertices.last().unwrap_or(&self.start_pos);
+            self.end_pos = to_image_pos(
+                &calc_pos_with_shfit_modifier(ui, pos, &last_pos, render_info),
+                &render_info.view,
+            )
+            .round();
+        }
 
+        let path = self.render_path(render_info);
         let stroke = Stroke::new(
             self.attributes.line_width * render_info.pixel_ratio,
             self.attributes.line_color,
         );
 
-        ui.painter()
-            .line_segment([render_start_pos, render_end_pos], stroke);
-
-        let tip_length = self.attributes.arrow_size * render_info.pixel_ratio;
-        let mut render_range = Rect::from_two_pos(render_start_pos, render_end_pos);
-        let mut render_arrow = |start_pos: Pos2, end_pos: Pos2| {
-            let rot = Rot2::from_angle(std::f32::consts::TAU / 15.0);
-            let vec = end_pos - start_pos;
-
-            let dir = vec.normalized();
-            let pos1 = end_pos - tip_length * (rot.inverse() * dir);
-            let pos2 = end_pos - tip_length * (rot * dir);
-            let pos3 = end_pos - tip_length * 0.7f32 * dir;
-            render_range.extend_with(pos1);
-            render_range.extend_with(pos2);
-            ui.painter().add(PathShape {
-                points: vec![pos1, end_pos, pos2, pos3],
-                closed: true,
-                fill: stroke.color,
-                stroke: stroke.into(),
-            });
+        let points = match path.as_slice() {
+            [start, control, end] if self.attributes.mode == LineMode::Curve => {
+                QuadraticBezierShape::from_points_stroke(
+                    [*start, *control, *end],
+                    false,
+                    Color32::TRANSPARENT,
+                    stroke,
+                )
+                .flatten(Some(0.5f32))
+            }
+            _ => path.clone(),
         };
+        let mut shapes = Vec::new();
+        self.attributes.stroke_style.paint(
+            &mut shapes,
+            points,
+            false,
+            stroke,
+            render_info.pixel_ratio,
+        );
 
-        if self.attributes.arrow_end {
-            render_arrow(render_start_pos, render_end_pos);
-        }
+        let head_length = self.attributes.head_length() * render_info.pixel_ratio;
+        let mut render_range = Rect::from_points(&path);
 
-        if self.attributes.arrow_start {
-            render_arrow(render_end_pos, render_start_pos);
+        // orient the arrows along the tangent, which is given by the nearest distinct point
+        let (first, last) = (path[0], path[path.len() - 1]);
+        if let Some(tangent_pos) = path.iter().rev().find(|p| **p != last) {
+            render_range = render_range.union(self.attributes.arrow_end.paint(
+                &mut shapes,
+                *tangent_pos,
+                last,
+                head_length,
+                stroke,
+            ));
+        }
+        if let Some(tangent_pos) = path.iter().find(|p| **p != first) {
+            render_range = render_range.union(self.attributes.arrow_start.paint(
+                &mut shapes,
+                *tangent_pos,
+                first,
+                head_length,
+                stroke,
+            ));
         }
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
 
         if is_active {
-            self.line_move.ui(
-                ui,
-                render_info,
-                &mut self.start_pos,
-                &mut self.end_pos,
-                0f32,
-            );
+            if self.drawing {
+                if ui.input(|i| i.key_pressed(Key::Enter)) {
+                    self.finish_drawing();
+                }
+            } else {
+                match self.attributes.mode {
+                    LineMode::Straight => self.line_move.ui(
+                        ui,
+                        render_info,
+                        &mut self.start_pos,
+                        &mut self.end_pos,
+                        &mut [],
+                        0f32,
+                    ),
+                    LineMode::Curve => self.bend_ui(ui, render_info),
+                    LineMode::Polyline => self.line_move.ui(
+                        ui,
+                        render_info,
+                        &mut self.start_pos,
+                        &mut self.end_pos,
+                        &mut self.vertices,
+                        0f32,
+                    ),
+                }
+            }
             true
         } else {
             hover_range(ui, render_range.expand(2f32), render_info.shot_mode)
@@ -141,8 +409,37 @@ impl Shape for Line {
         self.attributes.ui(ui);
     }
 
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        let points: Vec<Pos2> = [self.start_pos, self.end_pos]
+            .iter()
+            .chain(&self.bend_pos)
+            .chain(&self.vertices)
+            .map(|pos| to_screen_pos(pos, &render_info.view))
+            .collect();
+        Rect::from_points(&points)
+    }
+
     fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
         self.line_move
             .handle_move_end(ui, resp, render_info, &self.start_pos, &mut self.end_pos);
+        if self.drawing && resp.drag_stopped() {
+            self.vertices.push(self.end_pos);
+        }
+    }
+
+    fn on_create_click(&mut self, _pos: Pos2, render_info: &RenderInfo) -> bool {
+        if !self.drawing {
+            return false;
+        }
+        // the end point already follows the cursor, with the shift modifier applied
+        let last_pos = *self.vertices.last().unwrap_or(&self.start_pos);
+        let distance = to_screen_pos(&last_pos, &render_info.view)
+            - to_screen_pos(&self.end_pos, &render_info.view);
+        if distance.length() < FINISH_RANGE {
+            self.finish_drawing();
+        } else {
+            self.vertices.push(self.end_pos);
+        }
+        true
     }
 }
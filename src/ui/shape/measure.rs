@@ -0,0 +1,245 @@
+use eframe::egui::{
+    self, Align2, Color32, FontFamily, FontId, Label, Pos2, Rect, Response, Rgba, Slider, Stroke,
+    Ui, Vec2, Widget,
+};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+
+use crate::ui::{
+    color::color_edit,
+    move_resize::{LineMove, hover_range},
+    shape::{CreateAt, Shape, shadow::DropShadow},
+    utils::{to_image_pos, to_screen_pos},
+    window::RenderInfo,
+};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, IntoStaticStr)]
+pub enum MeasureConstraint {
+    #[default]
+    Free,
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone)]
+pub struct MeasureAttribute {
+    pub line_width: f32,
+    pub color: Rgba,
+    pub constraint: MeasureConstraint,
+    pub font_size: f32,
+    pub shadow: DropShadow,
+}
+
+impl Default for MeasureAttribute {
+    fn default() -> Self {
+        Self {
+            line_width: 2f32,
+            color: Rgba::RED,
+            constraint: MeasureConstraint::Free,
+            font_size: 14f32,
+            shadow: Default::default(),
+        }
+    }
+}
+
+impl MeasureAttribute {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Direction").selectable(false).ui(ui);
+        ui.horizontal(|ui| {
+            for constraint in MeasureConstraint::iter() {
+                ui.selectable_value(
+                    &mut self.constraint,
+                    constraint,
+                    <&'static str>::from(constraint),
+                );
+            }
+        });
+        ui.end_row();
+
+        Label::new("Line width").selectable(false).ui(ui);
+        Slider::new(&mut self.line_width, 1f32..=10f32).ui(ui);
+        ui.end_row();
+
+        Label::new("Color").selectable(false).ui(ui);
+        color_edit(ui, "measure_color", &mut self.color);
+        ui.end_row();
+
+        Label::new("Font Size").selectable(false).ui(ui);
+        Slider::new(&mut self.font_size, 8f32..=60f32).ui(ui);
+        ui.end_row();
+
+        self.shadow.ui(ui);
+    }
+}
+
+/// A dimension line labeled with its length in image pixels.
+#[derive(Clone)]
+pub struct Measure {
+    pub start_pos: Pos2,
+    pub end_pos: Pos2,
+
+    pub attributes: MeasureAttribute,
+
+    line_move: LineMove,
+}
+
+impl CreateAt for Measure {
+    type Attr = MeasureAttribute;
+    fn create_at(
+        pos: Pos2,
+        attributes: MeasureAttribute,
+        render_info: &RenderInfo,
+    ) -> Box<dyn Shape> {
+        let end_pos = match attributes.constraint {
+            MeasureConstraint::Vertical => pos + Vec2::new(0f32, 30f32),
+            _ => pos + Vec2::new(30f32, 0f32),
+        };
+        Box::new(Measure {
+            start_pos: to_image_pos(&pos, &render_info.view),
+            end_pos: to_image_pos(&end_pos, &render_info.view),
+            attributes,
+            line_move: Default::default(),
+        })
+    }
+}
+
+impl Measure {
+    /// Keep the end on the horizontal or vertical line through the start.
+    fn apply_constraint(&mut self) {
+        match self.attributes.constraint {
+            MeasureConstraint::Free => {}
+            MeasureConstraint::Horizontal => self.end_pos.y = self.start_pos.y,
+            MeasureConstraint::Vertical => self.end_pos.x = self.start_pos.x,
+        }
+    }
+
+    fn label(&self) -> String {
+        let offset = self.end_pos - self.start_pos;
+        let length = offset.length();
+        if offset.x.round() == 0f32 || offset.y.round() == 0f32 {
+            format!("{length:.0} px")
+        } else {
+            format!(
+                "{length:.1} px ({:.0} × {:.0})",
+                offset.x.abs(),
+                offset.y.abs()
+            )
+        }
+    }
+}
+
+impl Shape for Measure {
+    fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
+        self.apply_constraint();
+        let render_start_pos = to_screen_pos(&self.start_pos, &render_info.view);
+        let render_end_pos = to_screen_pos(&self.end_pos, &render_info.view);
+        let color: Color32 = self.attributes.color.into();
+        let stroke = Stroke::new(self.attributes.line_width * render_info.pixel_ratio, color);
+
+        let mut shapes = vec![egui::Shape::line_segment(
+            [render_start_pos, render_end_pos],
+            stroke,
+        )];
+        // ticks across both ends
+        let dir = (render_end_pos - render_start_pos).normalized();
+        let normal = if dir == Vec2::ZERO {
+            Vec2::Y
+        } else {
+            dir.rot90()
+        };
+        let tick = normal * (6f32 * render_info.pixel_ratio + stroke.width);
+        for pos in [render_start_pos, render_end_pos] {
+            shapes.push(egui::Shape::line_segment([pos - tick, pos + tick], stroke));
+        }
+
+        let label_range = paint_label(
+            ui,
+            &mut shapes,
+            self.label(),
+            render_start_pos.lerp(render_end_pos, 0.5f32),
+            self.attributes.font_size,
+            render_info.pixel_ratio,
+            color,
+        );
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
+
+        if is_active {
+            self.line_move.ui(
+                ui,
+                render_info,
+                &mut self.start_pos,
+                &mut self.end_pos,
+                &mut [],
+                0f32,
+            );
+            self.apply_constraint();
+            true
+        } else {
+            let render_range = Rect::from_two_pos(render_start_pos, render_end_pos)
+                .expand(tick.length())
+                .union(label_range);
+            hover_range(ui, render_range.expand(2f32), render_info.shot_mode)
+        }
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui, _render_info: &RenderInfo) {
+        self.attributes.ui(ui);
+    }
+
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        Rect::from_two_pos(
+            to_screen_pos(&self.start_pos, &render_info.view),
+            to_screen_pos(&self.end_pos, &render_info.view),
+        )
+    }
+
+    fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
+        self.line_move
+            .handle_move_end(ui, resp, render_info, &self.start_pos, &mut self.end_pos);
+        self.apply_constraint();
+    }
+}
+
+/// Paint `text` on a pill of `color` centered at `pos` into `shapes`, and return the pill range.
+/// The font size is in image pixels, scaled to the screen by `pixel_ratio`.
+pub fn paint_label(
+    ui: &Ui,
+    shapes: &mut Vec<egui::Shape>,
+    text: String,
+    pos: Pos2,
+    font_size: f32,
+    pixel_ratio: f32,
+    color: Color32,
+) -> Rect {
+    let galley = ui.painter().layout_no_wrap(
+        text,
+        FontId::new(font_size * pixel_ratio, FontFamily::Proportional),
+        contrast_color(color),
+    );
+    let label_range = Align2::CENTER_CENTER
+        .anchor_size(pos, galley.size())
+        .expand2(Vec2::new(6f32, 2f32) * pixel_ratio);
+    shapes.push(egui::Shape::rect_filled(
+        label_range,
+        label_range.height() / 2f32,
+        color,
+    ));
+    shapes.push(egui::Shape::galley(
+        label_range.center() - galley.size() / 2f32,
+        galley,
+        color,
+    ));
+    label_range
+}
+
+/// Black or white, whichever is easier to read on `background`.
+fn contrast_color(background: Color32) -> Color32 {
+    let [r, g, b, _] = background.to_array();
+    let luminance = 0.299f32 * r as f32 + 0.587f32 * g as f32 + 0.114f32 * b as f32;
+    if luminance > 150f32 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
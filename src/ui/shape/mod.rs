@@ -1,13 +1,20 @@
 use std::sync::atomic::AtomicU32;
 
-use eframe::egui::{Pos2, Response, Ui, ahash::HashMap};
+use eframe::egui::{Pos2, Rect, Response, Ui, ahash::HashMap};
 
-use crate::ui::window::RenderInfo;
+use crate::ui::{utils::snap_to_pixel, window::RenderInfo};
+pub mod callout;
 pub mod circle;
 pub mod line;
+pub mod measure;
 pub mod number;
 pub mod pen;
+pub mod picture;
+pub mod protractor;
 pub mod rectangle;
+pub mod shadow;
+pub mod stamp;
+pub mod stroke;
 pub mod text;
 
 pub trait Shape {
@@ -15,6 +22,12 @@ pub trait Shape {
     /// draw ui, and return `true` if it is actived
     fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool;
     fn toolbar_ui(&mut self, ui: &mut Ui, render_info: &RenderInfo);
+    /// the bounding rect on the screen, other shapes snap to its edges and center
+    fn bounding_rect(&self, ui: &Ui, render_info: &RenderInfo) -> Rect;
+    /// handle a click on the canvas while the shape is active, and return `true` if it is consumed
+    fn on_create_click(&mut self, _pos: Pos2, _render_info: &RenderInfo) -> bool {
+        false
+    }
 }
 
 pub trait CreateAt: Shape {
@@ -32,9 +45,12 @@ pub trait CreateAt: Shape {
         if resp.clicked()
             && let Some(pos) = resp.interact_pointer_pos()
         {
-            if active_shape_id.is_some() {
-                *active_shape_id = None;
+            if let Some(active_shape) = active_shape_id.and_then(|id| shapes.get_mut(&id)) {
+                if !active_shape.on_create_click(pos, render_info) {
+                    *active_shape_id = None;
+                }
             } else {
+                let pos = snap_to_pixel(&pos, &render_info.view);
                 let shape_id = ShapeId::new();
                 shapes.insert(shape_id, Self::create_at(pos, attr.clone(), render_info));
                 *active_shape_id = Some(shape_id);
@@ -43,11 +59,9 @@ pub trait CreateAt: Shape {
         if resp.drag_started()
             && let Some(pos) = resp.interact_pointer_pos()
         {
+            let pos = snap_to_pixel(&(pos - resp.drag_motion()), &render_info.view);
             let shape_id = ShapeId::new();
-            shapes.insert(
-                shape_id,
-                Self::create_at(pos - resp.drag_motion(), attr.clone(), render_info),
-            );
+            shapes.insert(shape_id, Self::create_at(pos, attr.clone(), render_info));
             *active_shape_id = Some(shape_id);
         } else if (resp.drag_stopped() || resp.dragged())
             && let Some(active_shape) = active_shape_id.and_then(|id| shapes.get_mut(&id))
@@ -4,19 +4,19 @@ use eframe::egui::TextEdit;
 use eframe::epaint::PathShape;
 use eframe::{
     egui::{
-        Align2, FontFamily, FontId, Label, Pos2, Rect, Response, Rgba, Slider, Stroke, Ui, Vec2,
-        Widget,
-        color_picker::{Alpha, color_edit_button_rgba},
-        emath::Rot2,
+        self, Align2, FontFamily, FontId, Label, Pos2, Rect, Response, Rgba, Slider, Ui, Vec2,
+        Widget, emath::Rot2,
     },
     epaint::PathStroke,
 };
 
+use crate::ui::color::color_edit;
 use crate::ui::move_resize::hover_range;
 use crate::ui::shape::CreateAt;
+use crate::ui::shape::shadow::DropShadow;
 use crate::ui::window::RenderInfo;
 use crate::{
-    ui::utils::{from_ratio_pos, to_ratio_pos},
+    ui::utils::{to_image_pos, to_screen_pos},
     ui::{move_resize::LineMove, shape::Shape},
 };
 
@@ -26,6 +26,7 @@ pub struct NumberAttribute {
     pub text_color: Rgba,
     pub circle_size: f32,
     pub font_size: f32,
+    pub shadow: DropShadow,
 }
 
 impl Default for NumberAttribute {
@@ -35,6 +36,7 @@ impl Default for NumberAttribute {
             text_color: Rgba::WHITE,
             circle_size: 20f32,
             font_size: 20f32,
+            shadow: Default::default(),
         }
     }
 }
@@ -42,11 +44,11 @@ impl Default for NumberAttribute {
 impl NumberAttribute {
     pub fn ui(&mut self, ui: &mut Ui) {
         Label::new("Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.fill_color, Alpha::OnlyBlend);
+        color_edit(ui, "number_fill_color", &mut self.fill_color);
         ui.end_row();
 
         Label::new("Text Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.text_color, Alpha::OnlyBlend);
+        color_edit(ui, "number_text_color", &mut self.text_color);
         ui.end_row();
 
         Label::new("Circle size").selectable(false).ui(ui);
@@ -56,6 +58,8 @@ impl NumberAttribute {
         Label::new("Font size").selectable(false).ui(ui);
         Slider::new(&mut self.font_size, 0f32..=50f32).ui(ui);
         ui.end_row();
+
+        self.shadow.ui(ui);
     }
 }
 
@@ -81,8 +85,8 @@ impl CreateAt for Number {
         static INC_NUMBER_BITMAP: AtomicU32 = AtomicU32::new(1);
         let n = INC_NUMBER_BITMAP.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
         Box::new(Number {
-            start_pos: to_ratio_pos(&pos, &render_info.screenshot_rect),
-            end_pos: to_ratio_pos(&pos, &render_info.screenshot_rect),
+            start_pos: to_image_pos(&pos, &render_info.view),
+            end_pos: to_image_pos(&pos, &render_info.view),
             attributes,
             line_move: Default::default(),
             number: n.to_string(),
@@ -92,37 +96,48 @@ impl CreateAt for Number {
 
 impl Shape for Number {
     fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
-        let render_start_pos = from_ratio_pos(&self.start_pos, &render_info.screenshot_rect);
-        let render_end_pos = from_ratio_pos(&self.end_pos, &render_info.screenshot_rect);
+        let render_start_pos = to_screen_pos(&self.start_pos, &render_info.view);
+        let render_end_pos = to_screen_pos(&self.end_pos, &render_info.view);
 
         let circle_radius = self.attributes.circle_size * render_info.pixel_ratio;
 
+        let mut shapes = Vec::new();
         let vec = render_end_pos - render_start_pos;
         if vec.length() >= circle_radius {
             let rot = Rot2::from_angle(std::f32::consts::TAU / 15.0);
             let vec = vec.normalized();
             let pos1 = render_start_pos + circle_radius * (rot * vec);
             let pos2 = render_start_pos + circle_radius * (rot.inverse() * vec);
-            ui.painter().add(PathShape {
+            shapes.push(egui::Shape::Path(PathShape {
                 points: vec![pos1, render_end_pos, pos2],
                 closed: true,
                 fill: self.attributes.fill_color.into(),
                 stroke: PathStroke::NONE,
-            });
+            }));
         }
-        ui.painter().circle(
+        shapes.push(egui::Shape::circle_filled(
             render_start_pos,
             circle_radius,
             self.attributes.fill_color,
-            Stroke::NONE,
-        );
-        ui.painter().text(
-            render_start_pos,
-            Align2::CENTER_CENTER,
-            &self.number,
-            FontId::new(self.attributes.font_size, FontFamily::Proportional),
+        ));
+        let galley = ui.painter().layout_no_wrap(
+            self.number.clone(),
+            FontId::new(
+                self.attributes.font_size * render_info.pixel_ratio,
+                FontFamily::Proportional,
+            ),
             self.attributes.text_color.into(),
         );
+        shapes.push(egui::Shape::galley(
+            Align2::CENTER_CENTER
+                .anchor_size(render_start_pos, galley.size())
+                .min,
+            galley,
+            self.attributes.text_color.into(),
+        ));
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
 
         if is_active {
             self.line_move.ui(
@@ -130,6 +145,7 @@ impl Shape for Number {
                 render_info,
                 &mut self.start_pos,
                 &mut self.end_pos,
+                &mut [],
                 circle_radius,
             );
             true
@@ -150,6 +166,13 @@ impl Shape for Number {
         ui.end_row();
     }
 
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        Rect::from_center_size(
+            to_screen_pos(&self.start_pos, &render_info.view),
+            Vec2::splat(self.attributes.circle_size * render_info.pixel_ratio * 2f32),
+        )
+    }
+
     fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
         self.line_move
             .handle_move_end(ui, resp, render_info, &self.start_pos, &mut self.end_pos);
@@ -1,13 +1,13 @@
 use eframe::egui::{
     Color32, CornerRadius, CursorIcon, Label, Pos2, Rect, Response, Rgba, Sense, Slider, Stroke,
     StrokeKind, Ui, Vec2, Widget,
-    color_picker::{Alpha, color_edit_button_rgba},
 };
 
 use crate::ui::{
+    color::color_edit,
     move_resize::{hover_range, key_arrow_to_offset},
-    shape::{CreateAt, Shape},
-    utils::{from_ratio_pos, to_ratio_pos, to_ratio_vec},
+    shape::{CreateAt, Shape, shadow::DropShadow, stroke::StrokeStyle},
+    utils::{to_image_pos, to_screen_pos},
     window::RenderInfo,
 };
 
@@ -15,6 +15,8 @@ use crate::ui::{
 pub struct PenAttribute {
     pub line_width: f32,
     pub line_color: Rgba,
+    pub stroke_style: StrokeStyle,
+    pub shadow: DropShadow,
 }
 
 impl Default for PenAttribute {
@@ -22,6 +24,8 @@ impl Default for PenAttribute {
         Self {
             line_width: 3f32,
             line_color: Rgba::RED,
+            stroke_style: Default::default(),
+            shadow: Default::default(),
         }
     }
 }
@@ -32,9 +36,13 @@ impl PenAttribute {
         Slider::new(&mut self.line_width, 1f32..=20f32).ui(ui);
         ui.end_row();
 
+        self.stroke_style.ui(ui);
+
         Label::new("Line Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.line_color, Alpha::OnlyBlend);
+        color_edit(ui, "pen_line_color", &mut self.line_color);
         ui.end_row();
+
+        self.shadow.ui(ui);
     }
 }
 
@@ -49,7 +57,7 @@ impl CreateAt for Pen {
     type Attr = PenAttribute;
     fn create_at(pos: Pos2, attributes: PenAttribute, render_info: &RenderInfo) -> Box<dyn Shape> {
         Box::new(Pen {
-            line: vec![to_ratio_pos(&pos, &render_info.screenshot_rect)],
+            line: vec![to_image_pos(&pos, &render_info.view)],
             attributes,
             drawing: true,
             draw_finish: false,
@@ -65,7 +73,7 @@ impl Shape for Pen {
         let points: Vec<Pos2> = self
             .line
             .iter()
-            .map(|p| from_ratio_pos(p, &render_info.screenshot_rect))
+            .map(|p| to_screen_pos(p, &render_info.view))
             .collect();
         let render_range = Rect::from_points(&points);
         let stroke = Stroke::new(
@@ -73,14 +81,21 @@ impl Shape for Pen {
             self.attributes.line_color,
         );
 
-        ui.painter().line(points, stroke);
+        let mut shapes = Vec::new();
+        self.attributes.stroke_style.paint(
+            &mut shapes,
+            points,
+            false,
+            stroke,
+            render_info.pixel_ratio,
+        );
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
 
         if is_active {
             if !self.drawing {
-                let mut offset = Vec2::ZERO;
-                if let Some(key_offset) = key_arrow_to_offset(ui) {
-                    offset = key_offset;
-                }
+                let mut offset = key_arrow_to_offset(ui).unwrap_or(Vec2::ZERO);
 
                 ui.painter().rect_stroke(
                     render_range.expand(2f32),
@@ -94,12 +109,11 @@ impl Shape for Pen {
                     .on_hover_cursor(CursorIcon::Grab);
                 if resp.dragged() {
                     ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
-                    offset = resp.drag_delta();
+                    offset = resp.drag_delta() / render_info.pixel_ratio;
                 }
                 if offset != Vec2::ZERO {
-                    let ratio_offset = to_ratio_vec(offset, &render_info.screenshot_rect);
                     for p in &mut self.line {
-                        *p += ratio_offset;
+                        *p += offset;
                     }
                 }
             }
@@ -118,12 +132,21 @@ impl Shape for Pen {
         self.attributes.ui(ui);
     }
 
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        let points: Vec<Pos2> = self
+            .line
+            .iter()
+            .map(|pos| to_screen_pos(pos, &render_info.view))
+            .collect();
+        Rect::from_points(&points)
+    }
+
     fn on_create_response(&mut self, _ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
         if resp.dragged()
             && let Some(currnet_pos) = resp.interact_pointer_pos()
         {
             self.line
-                .push(to_ratio_pos(&currnet_pos, &render_info.screenshot_rect));
+                .push(to_image_pos(&currnet_pos, &render_info.view));
         }
         if resp.drag_stopped() {
             self.drawing = false;
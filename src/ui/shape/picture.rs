@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{Receiver, channel};
+
+use eframe::egui::{
+    Checkbox, Color32, ColorImage, Context, Label, Rect, Response, Slider, TextureOptions, Ui,
+    Vec2, Widget,
+};
+
+use crate::ui::{
+    move_resize::{MoveResize, hover_rotated_range, rotated_corners},
+    shape::{Shape, shadow::DropShadow},
+    tiled::TiledTexture,
+    utils::{round_rect, to_image_rect, to_screen_rect},
+    window::RenderInfo,
+};
+
+/// An inserted picture never covers more than this part of the screenshot initially.
+const MAX_INITIAL_COVERAGE: f32 = 0.8f32;
+
+#[derive(Clone)]
+pub struct PictureAttribute {
+    pub opacity: f32,
+    pub lock_aspect: bool,
+    pub shadow: DropShadow,
+}
+
+impl Default for PictureAttribute {
+    fn default() -> Self {
+        Self {
+            opacity: 1f32,
+            lock_aspect: true,
+            shadow: Default::default(),
+        }
+    }
+}
+
+impl PictureAttribute {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Opacity").selectable(false).ui(ui);
+        Slider::new(&mut self.opacity, 0f32..=1f32).ui(ui);
+        ui.end_row();
+
+        Label::new("Aspect ratio").selectable(false).ui(ui);
+        Checkbox::new(&mut self.lock_aspect, "Lock aspect ratio").ui(ui);
+        ui.end_row();
+
+        self.shadow.ui(ui);
+    }
+}
+
+pub struct Picture {
+    pub range: Rect,
+    /// The rotation around the center in radians, clockwise.
+    pub angle: f32,
+
+    pub attributes: PictureAttribute,
+
+    /// Tiled, so that pictures larger than the maximum texture size can be inserted.
+    texture: TiledTexture,
+    move_resize: MoveResize,
+}
+
+impl Picture {
+    /// Place `image` at the center of the screenshot, in its original size if it fits.
+    pub fn insert(
+        ctx: &Context,
+        image: ColorImage,
+        attributes: PictureAttribute,
+        render_info: &RenderInfo,
+    ) -> Box<dyn Shape> {
+        let image_size = Vec2::new(image.width() as f32, image.height() as f32);
+        let mut size = image_size * render_info.pixel_ratio;
+        let max_size = render_info.screenshot_rect.size() * MAX_INITIAL_COVERAGE;
+        size *= (max_size.x / size.x).min(max_size.y / size.y).min(1f32);
+        let range = Rect::from_center_size(render_info.screenshot_rect.center(), size);
+        Box::new(Picture {
+            range: round_rect(to_image_rect(&range, &render_info.view)),
+            angle: 0f32,
+            attributes,
+            texture: TiledTexture::new(ctx, "picture", &image, TextureOptions::LINEAR),
+            move_resize: Default::default(),
+        })
+    }
+}
+
+impl Shape for Picture {
+    fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
+        let render_range = to_screen_rect(&self.range, &render_info.view);
+        let meshes = self.texture.meshes(
+            render_range,
+            self.angle,
+            Color32::WHITE.gamma_multiply(self.attributes.opacity),
+        );
+        self.attributes
+            .shadow
+            .paint(ui.painter(), meshes, render_info.pixel_ratio);
+
+        if is_active {
+            self.move_resize.aspect_ratio = self
+                .attributes
+                .lock_aspect
+                .then_some(self.texture.size().x / self.texture.size().y);
+            self.move_resize
+                .ui_rotated(ui, render_info, &mut self.range, &mut self.angle);
+            true
+        } else {
+            hover_rotated_range(
+                ui,
+                render_range.expand(2f32),
+                self.angle,
+                render_info.shot_mode,
+            )
+        }
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui, _render_info: &RenderInfo) {
+        self.attributes.ui(ui);
+    }
+
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        let render_range = to_screen_rect(&self.range, &render_info.view);
+        Rect::from_points(&rotated_corners(render_range, self.angle))
+    }
+
+    fn on_create_response(&mut self, _ui: &mut Ui, _resp: &Response, _render_info: &RenderInfo) {}
+}
+
+pub fn load_from_file(path: &Path) -> Result<ColorImage, Box<dyn std::error::Error>> {
+    let image = image::open(path)?.to_rgba8();
+    Ok(ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    ))
+}
+
+/// Decode an image of any size, the decoder's memory limits are lifted for huge screenshots.
+pub fn load_from_memory(bytes: &[u8]) -> Result<ColorImage, Box<dyn std::error::Error>> {
+    let mut reader = image::ImageReader::new(std::io::Cursor::new(bytes)).with_guessed_format()?;
+    reader.no_limits();
+    let image = reader.decode()?.to_rgba8();
+    Ok(ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    ))
+}
+
+pub fn load_from_clipboard() -> Result<ColorImage, Box<dyn std::error::Error>> {
+    let image = arboard::Clipboard::new()?.get_image()?;
+    Ok(ColorImage::from_rgba_unmultiplied(
+        [image.width, image.height],
+        &image.bytes,
+    ))
+}
+
+/// Ask for an image file with `zenity` in the background. `None` is sent if it is cancelled.
+pub fn choose_file(ctx: &Context) -> Receiver<Result<Option<PathBuf>, String>> {
+    let (sender, receiver) = channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let result = Command::new("zenity")
+            .arg("--file-selection")
+            .arg("--title=Insert Image")
+            .arg("--file-filter=Images | *.png *.jpg *.jpeg *.webp *.bmp *.gif *.tiff *.ppm")
+            .output()
+            .map(|output| {
+                let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                (output.status.success() && !path.is_empty()).then(|| PathBuf::from(path))
+            })
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    "Failed to open the file chooser: zenity is not installed".to_string()
+                }
+                _ => format!("Failed to open the file chooser: {e}"),
+            });
+        let _ = sender.send(result);
+        ctx.request_repaint();
+    });
+    receiver
+}
@@ -0,0 +1,164 @@
+use eframe::egui::{
+    self, Color32, Label, Pos2, Rect, Response, Rgba, Slider, Stroke, Ui, Vec2, Widget, emath::Rot2,
+};
+
+use crate::ui::{
+    color::color_edit,
+    move_resize::{LineMove, hover_range},
+    shape::{CreateAt, Shape, measure::paint_label, shadow::DropShadow},
+    utils::{to_image_pos, to_screen_pos},
+    window::RenderInfo,
+};
+
+/// The number of segments of the arc.
+const ARC_SEGMENTS: usize = 32;
+/// The angle between the arms of a new protractor.
+const DEFAULT_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+
+#[derive(Clone)]
+pub struct ProtractorAttribute {
+    pub line_width: f32,
+    pub color: Rgba,
+    pub font_size: f32,
+    pub shadow: DropShadow,
+}
+
+impl Default for ProtractorAttribute {
+    fn default() -> Self {
+        Self {
+            line_width: 2f32,
+            color: Rgba::RED,
+            font_size: 14f32,
+            shadow: Default::default(),
+        }
+    }
+}
+
+impl ProtractorAttribute {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Line width").selectable(false).ui(ui);
+        Slider::new(&mut self.line_width, 1f32..=10f32).ui(ui);
+        ui.end_row();
+
+        Label::new("Color").selectable(false).ui(ui);
+        color_edit(ui, "protractor_color", &mut self.color);
+        ui.end_row();
+
+        Label::new("Font Size").selectable(false).ui(ui);
+        Slider::new(&mut self.font_size, 8f32..=60f32).ui(ui);
+        ui.end_row();
+
+        self.shadow.ui(ui);
+    }
+}
+
+/// Two rays from a common vertex, labeled with the angle between them.
+#[derive(Clone)]
+pub struct Protractor {
+    pub vertex_pos: Pos2,
+    pub arm1_pos: Pos2,
+    pub arm2_pos: Pos2,
+
+    pub attributes: ProtractorAttribute,
+
+    line_move: LineMove,
+}
+
+impl CreateAt for Protractor {
+    type Attr = ProtractorAttribute;
+    fn create_at(
+        pos: Pos2,
+        attributes: ProtractorAttribute,
+        render_info: &RenderInfo,
+    ) -> Box<dyn Shape> {
+        let arm1_pos = pos + Vec2::new(60f32, 0f32);
+        Box::new(Protractor {
+            vertex_pos: to_image_pos(&pos, &render_info.view),
+            arm1_pos: to_image_pos(&arm1_pos, &render_info.view),
+            arm2_pos: to_image_pos(&second_arm(pos, arm1_pos), &render_info.view),
+            attributes,
+            line_move: Default::default(),
+        })
+    }
+}
+
+impl Shape for Protractor {
+    fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
+        let vertex = to_screen_pos(&self.vertex_pos, &render_info.view);
+        let arm1 = to_screen_pos(&self.arm1_pos, &render_info.view);
+        let arm2 = to_screen_pos(&self.arm2_pos, &render_info.view);
+        let color: Color32 = self.attributes.color.into();
+        let stroke = Stroke::new(self.attributes.line_width * render_info.pixel_ratio, color);
+
+        let mut shapes = vec![egui::Shape::line(vec![arm1, vertex, arm2], stroke)];
+
+        // the arc spans the smaller angle between the arms
+        let start_angle = (arm1 - vertex).angle();
+        let sweep = ((arm2 - vertex).angle() - start_angle + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        let radius = (30f32 * render_info.pixel_ratio)
+            .min((arm1 - vertex).length().min((arm2 - vertex).length()) * 0.5f32);
+        let arc: Vec<Pos2> = (0..=ARC_SEGMENTS)
+            .map(|i| {
+                let angle = start_angle + sweep * i as f32 / ARC_SEGMENTS as f32;
+                vertex + radius * Vec2::angled(angle)
+            })
+            .collect();
+        shapes.push(egui::Shape::line(arc, stroke));
+
+        let label_pos = vertex
+            + (radius + 20f32 * render_info.pixel_ratio) * Vec2::angled(start_angle + sweep / 2f32);
+        let label_range = paint_label(
+            ui,
+            &mut shapes,
+            format!("{:.1}°", sweep.abs().to_degrees()),
+            label_pos,
+            self.attributes.font_size,
+            render_info.pixel_ratio,
+            color,
+        );
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
+
+        if is_active {
+            self.line_move.ui(
+                ui,
+                render_info,
+                &mut self.vertex_pos,
+                &mut self.arm1_pos,
+                std::slice::from_mut(&mut self.arm2_pos),
+                0f32,
+            );
+            true
+        } else {
+            let render_range = Rect::from_points(&[vertex, arm1, arm2]).union(label_range);
+            hover_range(ui, render_range.expand(2f32), render_info.shot_mode)
+        }
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui, _render_info: &RenderInfo) {
+        self.attributes.ui(ui);
+    }
+
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        let points = [self.vertex_pos, self.arm1_pos, self.arm2_pos]
+            .map(|pos| to_screen_pos(&pos, &render_info.view));
+        Rect::from_points(&points)
+    }
+
+    fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
+        self.line_move
+            .handle_move_end(ui, resp, render_info, &self.vertex_pos, &mut self.arm1_pos);
+        // the second arm follows the first one while it is being created
+        let vertex = to_screen_pos(&self.vertex_pos, &render_info.view);
+        let arm1 = to_screen_pos(&self.arm1_pos, &render_info.view);
+        self.arm2_pos = to_image_pos(&second_arm(vertex, arm1), &render_info.view);
+    }
+}
+
+/// The end of the second arm of a new protractor, it opens counterclockwise from the first one.
+fn second_arm(vertex: Pos2, arm1: Pos2) -> Pos2 {
+    vertex + Rot2::from_angle(-DEFAULT_ANGLE) * (arm1 - vertex)
+}
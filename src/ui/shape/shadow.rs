@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use eframe::egui::{
+    self, Checkbox, Color32, DragValue, Label, Painter, Rgba, Slider, TextureId, Ui, Vec2, Widget,
+};
+use eframe::epaint::{ColorMode, WHITE_UV};
+
+use crate::ui::color::color_edit;
+
+/// The number of rings of copies used to approximate the blur.
+const BLUR_RINGS: usize = 3;
+/// The number of copies on each ring.
+const BLUR_RING_COPIES: usize = 8;
+
+/// A drop shadow painted beneath a shape, it works for any path so pen strokes and arrows get a
+/// shadow of their own outline.
+#[derive(Clone)]
+pub struct DropShadow {
+    pub enabled: bool,
+    /// the offset in image pixels
+    pub offset: Vec2,
+    /// the blur radius in image pixels
+    pub blur: f32,
+    pub color: Rgba,
+}
+
+impl Default for DropShadow {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            offset: Vec2::splat(4f32),
+            blur: 4f32,
+            color: Rgba::from_black_alpha(0.5f32),
+        }
+    }
+}
+
+impl DropShadow {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Shadow").selectable(false).ui(ui);
+        Checkbox::new(&mut self.enabled, "Drop shadow").ui(ui);
+        ui.end_row();
+
+        if !self.enabled {
+            return;
+        }
+
+        Label::new("Shadow offset").selectable(false).ui(ui);
+        ui.horizontal(|ui| {
+            DragValue::new(&mut self.offset.x)
+                .range(-50f32..=50f32)
+                .prefix("x: ")
+                .ui(ui);
+            DragValue::new(&mut self.offset.y)
+                .range(-50f32..=50f32)
+                .prefix("y: ")
+                .ui(ui);
+        });
+        ui.end_row();
+
+        Label::new("Shadow blur").selectable(false).ui(ui);
+        Slider::new(&mut self.blur, 0f32..=20f32).ui(ui);
+        ui.end_row();
+
+        Label::new("Shadow Color").selectable(false).ui(ui);
+        color_edit(ui, "shadow_color", &mut self.color);
+        ui.end_row();
+    }
+
+    /// Paint `shapes` and the shadow beneath them.
+    pub fn paint(&self, painter: &Painter, shapes: Vec<egui::Shape>, pixel_ratio: f32) {
+        if self.enabled {
+            let offset = self.offset * pixel_ratio;
+            let blur = self.blur * pixel_ratio;
+
+            // the blur is approximated by copies spread over a disc, their alpha is chosen so that
+            // the copies add up to the shadow color where all of them overlap
+            let mut copy_offsets = vec![offset];
+            if blur >= 0.5f32 {
+                for ring in 1..=BLUR_RINGS {
+                    let radius = blur * ring as f32 / BLUR_RINGS as f32;
+                    for i in 0..BLUR_RING_COPIES {
+                        let angle = std::f32::consts::TAU * i as f32 / BLUR_RING_COPIES as f32;
+                        copy_offsets.push(offset + radius * Vec2::angled(angle));
+                    }
+                }
+            }
+            let alpha = 1f32 - (1f32 - self.color.a()).powf(1f32 / copy_offsets.len() as f32);
+            let [r, g, b, _] = Color32::from(self.color).to_srgba_unmultiplied();
+            let color = Color32::from_rgba_unmultiplied(r, g, b, (alpha * 255f32).round() as u8);
+
+            let mut shadow = egui::Shape::Vec(shapes.clone());
+            tint(&mut shadow, color);
+            for copy_offset in copy_offsets {
+                let mut copy = shadow.clone();
+                copy.translate(copy_offset);
+                painter.add(copy);
+            }
+        }
+        painter.extend(shapes);
+    }
+}
+
+/// Paint everything of the shape with `color`.
+fn tint(shape: &mut egui::Shape, color: Color32) {
+    let tint_fill = |fill: &mut Color32| {
+        if *fill != Color32::TRANSPARENT {
+            *fill = color;
+        }
+    };
+    match shape {
+        egui::Shape::Vec(shapes) => {
+            for shape in shapes {
+                tint(shape, color);
+            }
+        }
+        egui::Shape::Circle(circle) => {
+            tint_fill(&mut circle.fill);
+            circle.stroke.color = color;
+        }
+        egui::Shape::Ellipse(ellipse) => {
+            tint_fill(&mut ellipse.fill);
+            ellipse.stroke.color = color;
+        }
+        egui::Shape::LineSegment { stroke, .. } => stroke.color = color,
+        egui::Shape::Path(path) => {
+            tint_fill(&mut path.fill);
+            path.stroke.color = ColorMode::Solid(color);
+        }
+        egui::Shape::Rect(rect) => {
+            tint_fill(&mut rect.fill);
+            rect.stroke.color = color;
+        }
+        egui::Shape::Text(text) => text.override_text_color = Some(color),
+        egui::Shape::Mesh(mesh) => {
+            // the shadow of a textured mesh is its silhouette
+            let mesh = Arc::make_mut(mesh);
+            mesh.texture_id = TextureId::default();
+            for vertex in &mut mesh.vertices {
+                vertex.uv = WHITE_UV;
+                vertex.color = color;
+            }
+        }
+        egui::Shape::QuadraticBezier(bezier) => {
+            tint_fill(&mut bezier.fill);
+            bezier.stroke.color = ColorMode::Solid(color);
+        }
+        egui::Shape::CubicBezier(bezier) => {
+            tint_fill(&mut bezier.fill);
+            bezier.stroke.color = ColorMode::Solid(color);
+        }
+        egui::Shape::Noop | egui::Shape::Callback(_) => {}
+    }
+}
@@ -0,0 +1,375 @@
+use std::sync::OnceLock;
+
+use ab_glyph::{Font, FontVec, GlyphImageFormat};
+use eframe::egui::{
+    self, Align2, Color32, ColorImage, Context, CornerRadius, FontFamily, FontId, Label, Mesh,
+    Pos2, Rect, Response, Rgba, Slider, Stroke, TextureHandle, TextureOptions, Ui, Vec2, Widget,
+};
+use eframe::epaint::PathShape;
+use log::warn;
+
+use crate::ui::{
+    color::color_edit,
+    move_resize::{MoveResize, ResizeMode, hover_range},
+    shape::{CreateAt, Shape, shadow::DropShadow},
+    utils::{to_image_rect, to_screen_rect},
+    window::RenderInfo,
+};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum StampKind {
+    Check,
+    Cross,
+    Warning,
+    Question,
+    Star,
+    ThumbsUp,
+    /// An emoji rendered with the color emoji font found by fontconfig.
+    Emoji(&'static str),
+}
+
+/// The vector symbols with the glyph shown in the palette.
+const SYMBOLS: [(StampKind, &str); 6] = [
+    (StampKind::Check, "✔"),
+    (StampKind::Cross, "✖"),
+    (StampKind::Warning, "⚠"),
+    (StampKind::Question, "❓"),
+    (StampKind::Star, "★"),
+    (StampKind::ThumbsUp, "👍"),
+];
+
+const EMOJIS: [&str; 16] = [
+    "😀", "😂", "😍", "🤔", "😢", "😡", "👎", "👏", "🙏", "❤", "🔥", "🎉", "💡", "🐛", "🚀", "👀",
+];
+
+/// Emojis are drawn in this color if there is no color emoji font.
+const FALLBACK_EMOJI_COLOR: Color32 = Color32::from_gray(0x30);
+
+/// The color emoji font, if there is one. It is unset until `load_emoji_font_in_background` has
+/// looked it up.
+static EMOJI_FONT: OnceLock<Option<FontVec>> = OnceLock::new();
+
+#[derive(Clone)]
+pub struct StampAttribute {
+    pub kind: StampKind,
+    pub color: Rgba,
+    /// the size of a stamp placed by clicking, in image pixels
+    pub size: f32,
+    pub shadow: DropShadow,
+}
+
+impl Default for StampAttribute {
+    fn default() -> Self {
+        Self {
+            kind: StampKind::Check,
+            color: Rgba::RED,
+            size: 48f32,
+            shadow: Default::default(),
+        }
+    }
+}
+
+impl StampAttribute {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Symbol").selectable(false).ui(ui);
+        ui.horizontal_wrapped(|ui| {
+            for (kind, glyph) in SYMBOLS {
+                ui.selectable_value(&mut self.kind, kind, glyph);
+            }
+        });
+        ui.end_row();
+
+        Label::new("Emoji").selectable(false).ui(ui);
+        ui.horizontal_wrapped(|ui| {
+            for emoji in EMOJIS {
+                ui.selectable_value(&mut self.kind, StampKind::Emoji(emoji), emoji);
+            }
+        });
+        ui.end_row();
+
+        // emojis bring their own colors
+        if !matches!(self.kind, StampKind::Emoji(_)) {
+            Label::new("Color").selectable(false).ui(ui);
+            color_edit(ui, "stamp_color", &mut self.color);
+            ui.end_row();
+        }
+
+        Label::new("Size").selectable(false).ui(ui);
+        Slider::new(&mut self.size, 8f32..=200f32).ui(ui);
+        ui.end_row();
+
+        self.shadow.ui(ui);
+    }
+}
+
+#[derive(Clone)]
+pub struct Stamp {
+    pub range: Rect,
+
+    pub attributes: StampAttribute,
+
+    move_resize: MoveResize,
+    /// The rasterized emoji, `None` if the emoji font has no image for it.
+    emoji_texture: Option<(&'static str, Option<TextureHandle>)>,
+}
+
+impl CreateAt for Stamp {
+    type Attr = StampAttribute;
+    fn create_at(
+        pos: Pos2,
+        attributes: StampAttribute,
+        render_info: &RenderInfo,
+    ) -> Box<dyn Shape> {
+        let size = attributes.size * render_info.pixel_ratio;
+        let mut move_resize = MoveResize::resize(pos);
+        move_resize.aspect_ratio = Some(1f32);
+        Box::new(Stamp {
+            range: to_image_rect(
+                &Rect::from_center_size(pos, Vec2::splat(size)),
+                &render_info.view,
+            ),
+            attributes,
+            move_resize,
+            emoji_texture: None,
+        })
+    }
+}
+
+impl Shape for Stamp {
+    fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
+        let render_range = to_screen_rect(&self.range, &render_info.view);
+        let shapes = match self.attributes.kind {
+            StampKind::Emoji(emoji) => self.emoji_shapes(ui, render_range, emoji),
+            _ => self.symbol_shapes(ui, render_range),
+        };
+        self.attributes
+            .shadow
+            .paint(ui.painter(), shapes, render_info.pixel_ratio);
+
+        if is_active {
+            self.move_resize.ui(ui, render_info, &mut self.range);
+            true
+        } else {
+            hover_range(ui, render_range.expand(2f32), render_info.shot_mode)
+        }
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui, _render_info: &RenderInfo) {
+        self.attributes.ui(ui);
+    }
+
+    fn bounding_rect(&self, _ui: &Ui, render_info: &RenderInfo) -> Rect {
+        to_screen_rect(&self.range, &render_info.view)
+    }
+
+    fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
+        self.move_resize
+            .handle_resize(ui, resp, render_info, &mut self.range, ResizeMode::None);
+    }
+}
+
+impl Stamp {
+    fn symbol_shapes(&self, ui: &Ui, range: Rect) -> Vec<egui::Shape> {
+        let color: Color32 = self.attributes.color.into();
+        let size = range.width().min(range.height());
+        // map a point of the unit square into the range
+        let at = |x: f32, y: f32| range.min + Vec2::new(x, y) * size;
+        let stroke = Stroke::new(size * 0.12f32, color);
+
+        match self.attributes.kind {
+            StampKind::Check => vec![egui::Shape::line(
+                vec![at(0.15, 0.55), at(0.4, 0.8), at(0.85, 0.25)],
+                stroke,
+            )],
+            StampKind::Cross => vec![
+                egui::Shape::line_segment([at(0.2, 0.2), at(0.8, 0.8)], stroke),
+                egui::Shape::line_segment([at(0.8, 0.2), at(0.2, 0.8)], stroke),
+            ],
+            StampKind::Warning => vec![
+                egui::Shape::convex_polygon(
+                    vec![at(0.5, 0.05), at(0.95, 0.9), at(0.05, 0.9)],
+                    color,
+                    Stroke::new(size * 0.04f32, color),
+                ),
+                egui::Shape::rect_filled(
+                    Rect::from_min_max(at(0.45, 0.35), at(0.55, 0.65)),
+                    CornerRadius::ZERO,
+                    Color32::WHITE,
+                ),
+                egui::Shape::circle_filled(at(0.5, 0.77), size * 0.05f32, Color32::WHITE),
+            ],
+            StampKind::Question => vec![
+                egui::Shape::circle_filled(range.center(), size * 0.45f32, color),
+                self.glyph_shape(ui, range, "?", size * 0.6f32, Color32::WHITE),
+            ],
+            StampKind::Star => {
+                let points: Vec<Pos2> = (0..10)
+                    .map(|i| {
+                        let radius = if i % 2 == 0 { 0.48f32 } else { 0.2f32 };
+                        let angle =
+                            std::f32::consts::TAU * i as f32 / 10f32 - std::f32::consts::FRAC_PI_2;
+                        range.center() + Vec2::angled(angle) * radius * size
+                    })
+                    .collect();
+                // the star is concave, but it can be filled as a fan around its center
+                let mut mesh = Mesh::default();
+                mesh.colored_vertex(range.center(), color);
+                for point in &points {
+                    mesh.colored_vertex(*point, color);
+                }
+                for i in 0..points.len() as u32 {
+                    mesh.add_triangle(0, i + 1, (i + 1) % points.len() as u32 + 1);
+                }
+                vec![
+                    egui::Shape::mesh(mesh),
+                    // smooth the edges of the mesh
+                    egui::Shape::Path(PathShape::closed_line(points, Stroke::new(1f32, color))),
+                ]
+            }
+            StampKind::ThumbsUp => vec![
+                // cuff
+                egui::Shape::rect_filled(
+                    Rect::from_min_max(at(0.08, 0.45), at(0.26, 0.92)),
+                    CornerRadius::same((size * 0.03f32) as u8),
+                    color,
+                ),
+                // fingers
+                egui::Shape::rect_filled(
+                    Rect::from_min_max(at(0.3, 0.42), at(0.88, 0.92)),
+                    CornerRadius::same((size * 0.1f32) as u8),
+                    color,
+                ),
+                // thumb
+                egui::Shape::convex_polygon(
+                    vec![at(0.3, 0.45), at(0.44, 0.12), at(0.6, 0.12), at(0.62, 0.45)],
+                    color,
+                    Stroke::NONE,
+                ),
+                egui::Shape::circle_filled(at(0.52, 0.14), size * 0.08f32, color),
+                // gaps between the fingers
+                egui::Shape::line_segment(
+                    [at(0.62, 0.58), at(0.88, 0.58)],
+                    Stroke::new(size * 0.025f32, Color32::WHITE),
+                ),
+                egui::Shape::line_segment(
+                    [at(0.62, 0.72), at(0.88, 0.72)],
+                    Stroke::new(size * 0.025f32, Color32::WHITE),
+                ),
+            ],
+            StampKind::Emoji(_) => Vec::new(),
+        }
+    }
+
+    /// The emoji as a textured mesh, or as a glyph of egui's monochrome emoji font as a fallback.
+    fn emoji_shapes(&mut self, ui: &Ui, range: Rect, emoji: &'static str) -> Vec<egui::Shape> {
+        // the glyph is shown until the font is loaded, the texture is cached afterwards
+        if EMOJI_FONT.get().is_some()
+            && self
+                .emoji_texture
+                .as_ref()
+                .is_none_or(|(cached, _)| *cached != emoji)
+        {
+            let texture = rasterize_emoji(emoji).map(|image| {
+                ui.ctx()
+                    .load_texture(format!("emoji-{emoji}"), image, TextureOptions::LINEAR)
+            });
+            self.emoji_texture = Some((emoji, texture));
+        }
+        let size = range.width().min(range.height());
+        let Some((_, Some(texture))) = &self.emoji_texture else {
+            return vec![self.glyph_shape(ui, range, emoji, size * 0.8f32, FALLBACK_EMOJI_COLOR)];
+        };
+        let image_size = texture.size_vec2();
+        let scale = 0.9f32 * size / image_size.x.max(image_size.y);
+        let mut mesh = Mesh::with_texture(texture.id());
+        mesh.add_rect_with_uv(
+            Rect::from_center_size(range.center(), image_size * scale),
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1f32, 1f32)),
+            Color32::WHITE,
+        );
+        vec![egui::Shape::mesh(mesh)]
+    }
+
+    /// `text` centered in `range`, `font_size` is in screen points like `range`, so it follows
+    /// the view together with the stamp.
+    fn glyph_shape(
+        &self,
+        ui: &Ui,
+        range: Rect,
+        text: &str,
+        font_size: f32,
+        color: Color32,
+    ) -> egui::Shape {
+        let galley = ui.painter().layout_no_wrap(
+            text.to_string(),
+            FontId::new(font_size, FontFamily::Proportional),
+            color,
+        );
+        egui::Shape::galley(
+            Align2::CENTER_CENTER
+                .anchor_size(range.center(), galley.size())
+                .min,
+            galley,
+            color,
+        )
+    }
+}
+
+/// Look up the color emoji font on a background thread, fontconfig and reading the font may take
+/// a while. Stamps are repainted with it once it is loaded.
+pub fn load_emoji_font_in_background(ctx: &Context) {
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        EMOJI_FONT.get_or_init(load_emoji_font);
+        ctx.request_repaint();
+    });
+}
+
+/// Find a color emoji font with fontconfig, fontconfig falls back to any font if none of the
+/// families is installed.
+fn load_emoji_font() -> Option<FontVec> {
+    let fc = fontconfig::Fontconfig::new()?;
+    ["Noto Color Emoji", "emoji"]
+        .iter()
+        .filter_map(|family| fc.find(family, None))
+        .find_map(|font| {
+            let data = std::fs::read(&font.path)
+                .inspect_err(|e| warn!("Read emoji font {:?} failed. Reason: {}", font.path, e))
+                .ok()?;
+            let font =
+                FontVec::try_from_vec_and_index(data, font.index.unwrap_or(0) as u32).ok()?;
+            let has_bitmaps = font
+                .glyph_raster_image2(font.glyph_id('😀'), u16::MAX)
+                .is_some();
+            has_bitmaps.then_some(font)
+        })
+}
+
+/// The largest bitmap of `emoji` in the color emoji font.
+fn rasterize_emoji(emoji: &str) -> Option<ColorImage> {
+    let font = EMOJI_FONT.get()?.as_ref()?;
+    let glyph_id = font.glyph_id(emoji.chars().next()?);
+    let image = font.glyph_raster_image2(glyph_id, u16::MAX)?;
+    let size = [image.width as usize, image.height as usize];
+    match image.format {
+        GlyphImageFormat::Png => {
+            let png = image::load_from_memory_with_format(image.data, image::ImageFormat::Png)
+                .ok()?
+                .to_rgba8();
+            Some(ColorImage::from_rgba_unmultiplied(
+                [png.width() as usize, png.height() as usize],
+                png.as_raw(),
+            ))
+        }
+        GlyphImageFormat::BitmapPremulBgra32 => {
+            let pixels = image
+                .data
+                .get(..size[0] * size[1] * 4)?
+                .chunks_exact(4)
+                .map(|p| Color32::from_rgba_premultiplied(p[2], p[1], p[0], p[3]))
+                .collect();
+            Some(ColorImage::new(size, pixels))
+        }
+        _ => None,
+    }
+}
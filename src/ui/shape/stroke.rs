@@ -0,0 +1,112 @@
+use eframe::egui::{ComboBox, Label, Pos2, Rect, Shape, Slider, Stroke, Ui, Vec2, Widget};
+use eframe::epaint::{CornerRadiusF32, PathShape, tessellator::path::rounded_rectangle};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, IntoStaticStr)]
+pub enum StrokePattern {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    #[strum(serialize = "Dash-dot")]
+    DashDot,
+}
+
+/// The pattern used to draw the outline of a shape.
+#[derive(Clone)]
+pub struct StrokeStyle {
+    pub pattern: StrokePattern,
+    /// the length of a dash in image pixels
+    pub dash_length: f32,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            pattern: StrokePattern::Solid,
+            dash_length: 12f32,
+        }
+    }
+}
+
+impl StrokeStyle {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        Label::new("Line style").selectable(false).ui(ui);
+        ComboBox::from_id_salt("stroke_pattern")
+            .selected_text(<&'static str>::from(self.pattern))
+            .show_ui(ui, |ui| {
+                for pattern in StrokePattern::iter() {
+                    ui.selectable_value(&mut self.pattern, pattern, <&'static str>::from(pattern));
+                }
+            });
+        ui.end_row();
+
+        if matches!(self.pattern, StrokePattern::Dashed | StrokePattern::DashDot) {
+            Label::new("Dash length").selectable(false).ui(ui);
+            Slider::new(&mut self.dash_length, 2f32..=50f32).ui(ui);
+            ui.end_row();
+        }
+    }
+
+    /// Paint the path through `points` with this pattern into `shapes`.
+    pub fn paint(
+        &self,
+        shapes: &mut Vec<Shape>,
+        mut points: Vec<Pos2>,
+        closed: bool,
+        stroke: Stroke,
+        pixel_ratio: f32,
+    ) {
+        // the patterns are painted along an open path, so close it manually
+        if closed
+            && self.pattern != StrokePattern::Solid
+            && let Some(first) = points.first()
+        {
+            points.push(*first);
+        }
+        let dash_length = self.dash_length * pixel_ratio;
+        let gap_length = dash_length * 0.5f32 + stroke.width;
+        let pattern_shapes = match self.pattern {
+            StrokePattern::Solid => vec![Shape::Path(PathShape {
+                points,
+                closed,
+                fill: Default::default(),
+                stroke: stroke.into(),
+            })],
+            StrokePattern::Dashed => Shape::dashed_line(&points, stroke, dash_length, gap_length),
+            StrokePattern::Dotted => Shape::dotted_line(
+                &points,
+                stroke.color,
+                stroke.width * 2f32,
+                stroke.width / 2f32,
+            ),
+            StrokePattern::DashDot => Shape::dashed_line_with_offset(
+                &points,
+                stroke,
+                &[dash_length, stroke.width],
+                &[gap_length, gap_length],
+                0f32,
+            ),
+        };
+        shapes.extend(pattern_shapes);
+    }
+}
+
+/// The outline of a rounded rectangle.
+pub fn rounded_rect_points(rect: Rect, radius: f32) -> Vec<Pos2> {
+    let mut points = Vec::new();
+    rounded_rectangle(&mut points, rect, CornerRadiusF32::same(radius));
+    points
+}
+
+/// The outline of an ellipse.
+pub fn ellipse_points(center: Pos2, radius: Vec2) -> Vec<Pos2> {
+    // enough segments to look smooth at any size
+    let count = (radius.max_elem().sqrt() * 8f32).clamp(16f32, 256f32) as usize;
+    (0..count)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / count as f32;
+            center + Vec2::new(angle.cos() * radius.x, angle.sin() * radius.y)
+        })
+        .collect()
+}
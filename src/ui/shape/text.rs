@@ -1,13 +1,15 @@
+use std::sync::Arc;
+
 use eframe::egui::{
-    Align2, Color32, CornerRadius, CursorIcon, FontFamily, FontId, FontSelection, Label, Pos2,
-    Response, Rgba, Sense, Slider, Stroke, StrokeKind, TextEdit, Ui, Widget,
-    color_picker::{Alpha, color_edit_button_rgba},
+    self, Align2, Color32, CursorIcon, FontFamily, FontId, FontSelection, Galley, Label, Pos2,
+    Rect, Response, Rgba, Sense, Slider, Stroke, TextEdit, Ui, Widget, epaint::TextShape,
 };
 
 use crate::ui::{
-    move_resize::{hover_range, key_arrow_to_offset},
-    shape::{CreateAt, Shape},
-    utils::{from_ratio_pos, to_ratio_pos},
+    color::color_edit,
+    move_resize::{hover_rotated_range, key_arrow_to_offset, rotated_corners, rotation_handle},
+    shape::{CreateAt, Shape, shadow::DropShadow},
+    utils::{to_image_pos, to_screen_pos},
     window::RenderInfo,
 };
 
@@ -16,6 +18,7 @@ pub struct TextAttribute {
     color: Rgba,
     text: String,
     size: f32,
+    shadow: DropShadow,
 }
 
 impl Default for TextAttribute {
@@ -24,6 +27,7 @@ impl Default for TextAttribute {
             color: Rgba::RED,
             text: "Edit Text here".to_string(),
             size: 20f32,
+            shadow: Default::default(),
         }
     }
 }
@@ -31,7 +35,7 @@ impl Default for TextAttribute {
 impl TextAttribute {
     pub fn ui(&mut self, ui: &mut Ui, font_family: FontFamily) {
         Label::new("Text Color").selectable(false).ui(ui);
-        color_edit_button_rgba(ui, &mut self.color, Alpha::OnlyBlend);
+        color_edit(ui, "text_color", &mut self.color);
         ui.end_row();
 
         Label::new("Text").selectable(false).ui(ui);
@@ -43,11 +47,15 @@ impl TextAttribute {
         Label::new("Font Size").selectable(false).ui(ui);
         Slider::new(&mut self.size, 0f32..=200f32).ui(ui);
         ui.end_row();
+
+        self.shadow.ui(ui);
     }
 }
 
 pub struct Text {
     pub pos: Pos2,
+    /// The rotation around the center in radians, clockwise.
+    pub angle: f32,
 
     pub attributes: TextAttribute,
 }
@@ -63,43 +71,67 @@ impl CreateAt for Text {
             attributes.text = "Edit Text here".to_string();
         }
         Box::new(Text {
-            pos: to_ratio_pos(&pos, &render_info.screenshot_rect),
+            pos: to_image_pos(&pos, &render_info.view),
+            angle: 0f32,
             attributes,
         })
     }
 }
 
+impl Text {
+    /// The text laid out on the screen, the font size is in image pixels.
+    fn galley(&self, ui: &Ui, render_info: &RenderInfo) -> Arc<Galley> {
+        ui.painter().layout_no_wrap(
+            self.attributes.text.clone(),
+            FontId::new(
+                self.attributes.size * render_info.pixel_ratio,
+                render_info.user_font.clone(),
+            ),
+            self.attributes.color.into(),
+        )
+    }
+}
+
 impl Shape for Text {
     fn ui(&mut self, ui: &mut Ui, is_active: bool, render_info: &RenderInfo) -> bool {
-        let render_pos = from_ratio_pos(&self.pos, &render_info.screenshot_rect);
-
-        let render_range = ui.painter().text(
-            render_pos,
-            Align2::CENTER_CENTER,
-            self.attributes.text.as_str(),
-            FontId::new(self.attributes.size, render_info.user_font.clone()),
-            self.attributes.color.into(),
+        let render_pos = to_screen_pos(&self.pos, &render_info.view);
+
+        let galley = self.galley(ui, render_info);
+        let render_range = Align2::CENTER_CENTER.anchor_size(render_pos, galley.size());
+        self.attributes.shadow.paint(
+            ui.painter(),
+            vec![
+                TextShape::new(render_range.min, galley, self.attributes.color.into())
+                    .with_angle_and_anchor(self.angle, Align2::CENTER_CENTER)
+                    .into(),
+            ],
+            render_info.pixel_ratio,
         );
 
         if is_active {
             if let Some(offset) = key_arrow_to_offset(ui) {
-                self.pos = to_ratio_pos(&(render_pos + offset), &render_info.screenshot_rect);
+                self.pos += offset;
             }
 
-            ui.painter().rect_stroke(
-                render_range.expand(2f32),
-                CornerRadius::ZERO,
+            let corners = rotated_corners(render_range.expand(2f32), self.angle);
+            ui.painter().add(egui::Shape::closed_line(
+                corners.to_vec(),
                 Stroke::new(1f32, Color32::from_gray(0xee)),
-                StrokeKind::Outside,
-            );
+            ));
 
             let response = ui
-                .allocate_rect(render_range.expand(2f32), Sense::click_and_drag())
+                .allocate_rect(Rect::from_points(&corners), Sense::click_and_drag())
                 .on_hover_cursor(CursorIcon::Grab);
             self.on_create_response(ui, &response, render_info);
+            rotation_handle(ui, render_range.expand(2f32), &mut self.angle);
             true
         } else {
-            hover_range(ui, render_range.expand(2f32), render_info.shot_mode)
+            hover_rotated_range(
+                ui,
+                render_range.expand(2f32),
+                self.angle,
+                render_info.shot_mode,
+            )
         }
     }
 
@@ -107,12 +139,63 @@ impl Shape for Text {
         self.attributes.ui(ui, render_info.user_font.clone());
     }
 
+    fn bounding_rect(&self, ui: &Ui, render_info: &RenderInfo) -> Rect {
+        let galley = self.galley(ui, render_info);
+        let render_range = Align2::CENTER_CENTER
+            .anchor_size(to_screen_pos(&self.pos, &render_info.view), galley.size());
+        Rect::from_points(&rotated_corners(render_range, self.angle))
+    }
+
     fn on_create_response(&mut self, ui: &mut Ui, resp: &Response, render_info: &RenderInfo) {
         if resp.dragged() {
             ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
-            self.pos = to_ratio_pos(
-                &(from_ratio_pos(&self.pos, &render_info.screenshot_rect) + resp.drag_delta()),
-                &render_info.screenshot_rect,
+            self.pos += resp.drag_delta() / render_info.pixel_ratio;
+        }
+        if resp.drag_stopped() {
+            self.pos = self.pos.round();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::{CentralPanel, Context, RawInput, Vec2, emath::TSTransform};
+
+    use super::*;
+
+    fn bounding_size(pixel_ratio: f32) -> Vec2 {
+        let text = Text {
+            pos: Pos2::new(100f32, 100f32),
+            angle: 0f32,
+            attributes: TextAttribute::default(),
+        };
+        let render_info = RenderInfo {
+            view: TSTransform::from_scaling(pixel_ratio),
+            screenshot_rect: Rect::ZERO,
+            pixel_ratio,
+            user_font: FontFamily::Proportional,
+            shot_mode: false,
+            snap_targets: Default::default(),
+        };
+        let mut size = Vec2::ZERO;
+        let _ = Context::default().run(RawInput::default(), |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                size = text.bounding_rect(ui, &render_info).size();
+            });
+        });
+        size
+    }
+
+    #[test]
+    fn bounding_rect_scales_with_pixel_ratio() {
+        let size = bounding_size(1f32);
+        assert!(size.min_elem() > 0f32);
+        for pixel_ratio in [0.5f32, 2f32, 4f32] {
+            // glyphs are laid out on whole pixels, so small sizes are off by a few percent
+            let scaled = bounding_size(pixel_ratio) / pixel_ratio;
+            assert!(
+                ((scaled - size) / size).abs().max_elem() < 0.05f32,
+                "{scaled:?} != {size:?} at {pixel_ratio}"
             );
         }
     }
@@ -0,0 +1,239 @@
+use eframe::egui::{
+    Checkbox, Color32, DragValue, Painter, Pos2, Rangef, Rect, Response, Stroke, Ui, Vec2, Widget,
+};
+
+/// Snap when an edge or center is closer than this to a target.
+const SNAP_DISTANCE: f32 = 6f32;
+const GUIDE_STROKE: Stroke = Stroke {
+    width: 1f32,
+    color: Color32::from_rgb(0xff, 0x00, 0xff),
+};
+const GRID_STROKE: Stroke = Stroke {
+    width: 1f32,
+    color: Color32::from_rgba_premultiplied(0x40, 0x40, 0x40, 0x40),
+};
+/// Grid lines closer than this on the screen are thinned out.
+const MIN_GRID_LINE_DISTANCE: f32 = 4f32;
+
+/// Lines every `spacing` image pixels over the screenshot, shapes snap to them when enabled.
+#[derive(Clone)]
+pub struct Grid {
+    pub enabled: bool,
+    /// the spacing in image pixels
+    pub spacing: f32,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 8f32,
+        }
+    }
+}
+
+impl Grid {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            Checkbox::new(&mut self.enabled, "Grid").ui(ui);
+            ui.add_enabled(
+                self.enabled,
+                DragValue::new(&mut self.spacing)
+                    .range(2f32..=500f32)
+                    .suffix(" px"),
+            );
+        });
+    }
+
+    pub fn paint(&self, painter: &Painter, screenshot_rect: Rect, pixel_ratio: f32) {
+        if !self.enabled {
+            return;
+        }
+        let mut spacing = self.spacing * pixel_ratio;
+        while spacing < MIN_GRID_LINE_DISTANCE {
+            spacing *= 2f32;
+        }
+        // only the lines in the visible part, a zoomed in screenshot may have thousands of them
+        let visible = screenshot_rect.intersect(painter.clip_rect());
+        if !visible.is_positive() {
+            return;
+        }
+        for x in grid_lines(screenshot_rect.left(), visible.x_range(), spacing) {
+            painter.vline(x, visible.y_range(), GRID_STROKE);
+        }
+        for y in grid_lines(screenshot_rect.top(), visible.y_range(), spacing) {
+            painter.hline(visible.x_range(), y, GRID_STROKE);
+        }
+    }
+}
+
+/// The positions of the lines every `spacing` after `start` which lie inside of `visible`.
+fn grid_lines(start: f32, visible: Rangef, spacing: f32) -> impl Iterator<Item = f32> {
+    let first = ((visible.min - start) / spacing).floor().max(0f32) as u32 + 1;
+    (first..)
+        .map(move |i| start + i as f32 * spacing)
+        .skip_while(move |x| *x < visible.min)
+        .take_while(move |x| *x < visible.max)
+}
+
+/// The edges and centers which moved and resized shapes snap to, holding alt disables snapping.
+/// Positions which snap to none of them snap to the grid if it is enabled.
+#[derive(Clone)]
+pub struct SnapTargets {
+    /// guides are drawn across this rect, and the grid starts at its left top
+    pub bounds: Rect,
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    /// the grid spacing on the screen
+    grid: Option<f32>,
+}
+
+impl Default for SnapTargets {
+    fn default() -> Self {
+        Self {
+            bounds: Rect::NOTHING,
+            xs: Vec::new(),
+            ys: Vec::new(),
+            grid: None,
+        }
+    }
+}
+
+impl SnapTargets {
+    pub fn new(bounds: Rect) -> Self {
+        let mut targets = Self {
+            bounds,
+            ..Default::default()
+        };
+        targets.add_rect(bounds);
+        targets
+    }
+
+    pub fn add_rect(&mut self, rect: Rect) {
+        self.xs.extend([rect.left(), rect.center().x, rect.right()]);
+        self.ys.extend([rect.top(), rect.center().y, rect.bottom()]);
+    }
+
+    pub fn set_grid(&mut self, grid: &Grid, pixel_ratio: f32) {
+        self.grid = grid.enabled.then_some(grid.spacing * pixel_ratio);
+    }
+
+    /// The offsets which move `x` and `y` onto the nearest grid lines.
+    fn grid_offset(&self, x: f32, y: f32) -> (Option<f32>, Option<f32>) {
+        let Some(spacing) = self.grid else {
+            return (None, None);
+        };
+        let offset = |value: f32, origin: f32| {
+            let value = value - origin;
+            (value / spacing).round() * spacing - value
+        };
+        (
+            Some(offset(x, self.bounds.left())),
+            Some(offset(y, self.bounds.top())),
+        )
+    }
+
+    /// Snap a dragged point to the nearest targets and show the guides.
+    pub fn snap_pos(&self, resp: &Response, pos: Pos2) -> Pos2 {
+        if self.disabled(resp) {
+            return pos;
+        }
+        let dx = nearest(&self.xs, &[pos.x]);
+        let dy = nearest(&self.ys, &[pos.y]);
+        let (grid_dx, grid_dy) = self.grid_offset(pos.x, pos.y);
+        let snapped = pos
+            + Vec2::new(
+                dx.or(grid_dx).unwrap_or(0f32),
+                dy.or(grid_dy).unwrap_or(0f32),
+            );
+        self.paint_guides(
+            resp,
+            dx.map(|_| snapped.x).as_slice(),
+            dy.map(|_| snapped.y).as_slice(),
+        );
+        snapped
+    }
+
+    /// Move a dragged rect so that one of its edges or its center snaps to the nearest targets, and
+    /// show the guides.
+    pub fn snap_rect(&self, resp: &Response, rect: Rect) -> Rect {
+        if self.disabled(resp) {
+            return rect;
+        }
+        let dx = nearest(&self.xs, &[rect.left(), rect.center().x, rect.right()]);
+        let dy = nearest(&self.ys, &[rect.top(), rect.center().y, rect.bottom()]);
+        let (grid_dx, grid_dy) = self.grid_offset(rect.left(), rect.top());
+        let snapped = rect.translate(Vec2::new(
+            dx.or(grid_dx).unwrap_or(0f32),
+            dy.or(grid_dy).unwrap_or(0f32),
+        ));
+
+        // show every line the snapped rect is aligned to
+        let aligned = |targets: &[f32], values: [f32; 3]| -> Vec<f32> {
+            values
+                .into_iter()
+                .filter(|v| targets.iter().any(|t| (t - v).abs() < 0.5f32))
+                .collect()
+        };
+        let xs = if dx.is_some() {
+            aligned(
+                &self.xs,
+                [snapped.left(), snapped.center().x, snapped.right()],
+            )
+        } else {
+            Vec::new()
+        };
+        let ys = if dy.is_some() {
+            aligned(
+                &self.ys,
+                [snapped.top(), snapped.center().y, snapped.bottom()],
+            )
+        } else {
+            Vec::new()
+        };
+        self.paint_guides(resp, &xs, &ys);
+        snapped
+    }
+
+    fn disabled(&self, resp: &Response) -> bool {
+        resp.ctx.input(|i| i.modifiers.alt)
+    }
+
+    fn paint_guides(&self, resp: &Response, xs: &[f32], ys: &[f32]) {
+        let painter = resp.ctx.layer_painter(resp.layer_id);
+        for x in xs {
+            painter.vline(*x, self.bounds.y_range(), GUIDE_STROKE);
+        }
+        for y in ys {
+            painter.hline(self.bounds.x_range(), *y, GUIDE_STROKE);
+        }
+    }
+}
+
+/// The smallest offset which moves one of `values` onto a target within the snap distance.
+fn nearest(targets: &[f32], values: &[f32]) -> Option<f32> {
+    values
+        .iter()
+        .flat_map(|value| targets.iter().map(move |target| target - value))
+        .filter(|offset| offset.abs() < SNAP_DISTANCE)
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_lines_in_the_visible_range() {
+        let lines: Vec<f32> = grid_lines(0f32, Rangef::new(0f32, 40f32), 10f32).collect();
+        assert_eq!(lines, [10f32, 20f32, 30f32]);
+
+        // zoomed in, only the handful of lines on the screen out of a million
+        let lines: Vec<f32> = grid_lines(-1e6f32, Rangef::new(95f32, 130f32), 10f32).collect();
+        assert_eq!(lines, [100f32, 110f32, 120f32]);
+
+        // the visible range before the start of the grid
+        let lines: Vec<f32> = grid_lines(50f32, Rangef::new(0f32, 75f32), 10f32).collect();
+        assert_eq!(lines, [60f32, 70f32]);
+    }
+}
@@ -0,0 +1,90 @@
+use eframe::egui::{
+    Color32, ColorImage, Context, Mesh, Painter, Pos2, Rect, Shape, TextureHandle, TextureOptions,
+    Vec2,
+    emath::{Rot2, TSTransform},
+};
+
+use crate::ui::utils::to_screen_rect;
+
+const FULL_UV: Rect = Rect {
+    min: Pos2 { x: 0f32, y: 0f32 },
+    max: Pos2 { x: 1f32, y: 1f32 },
+};
+
+/// An image uploaded as a grid of textures, so that it may be larger than the maximum texture
+/// size of the GPU.
+pub struct TiledTexture {
+    size: Vec2,
+    /// the textures with their ranges in image pixels
+    tiles: Vec<(Rect, TextureHandle)>,
+}
+
+impl TiledTexture {
+    pub fn new(ctx: &Context, name: &str, image: &ColorImage, options: TextureOptions) -> Self {
+        let tile_side = ctx.input(|i| i.max_texture_side).max(1);
+        let [width, height] = image.size;
+        let mut tiles = Vec::new();
+        for top in (0..height).step_by(tile_side) {
+            for left in (0..width).step_by(tile_side) {
+                let tile_width = tile_side.min(width - left);
+                let tile_height = tile_side.min(height - top);
+                let mut pixels = Vec::with_capacity(tile_width * tile_height);
+                for y in top..top + tile_height {
+                    let row = y * width + left;
+                    pixels.extend_from_slice(&image.pixels[row..row + tile_width]);
+                }
+                let range = Rect::from_min_size(
+                    Pos2::new(left as f32, top as f32),
+                    Vec2::new(tile_width as f32, tile_height as f32),
+                );
+                let texture = ctx.load_texture(
+                    format!("{name}-{left}-{top}"),
+                    ColorImage::new([tile_width, tile_height], pixels),
+                    options,
+                );
+                tiles.push((range, texture));
+            }
+        }
+        Self {
+            size: Vec2::new(width as f32, height as f32),
+            tiles,
+        }
+    }
+
+    /// The size in image pixels.
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// The tiles as meshes stretched over `range` on the screen and rotated by `angle` around its
+    /// center, clockwise in radians.
+    pub fn meshes(&self, range: Rect, angle: f32, tint: Color32) -> Vec<Shape> {
+        let scale = range.size() / self.size;
+        self.tiles
+            .iter()
+            .map(|(tile, texture)| {
+                let mut mesh = Mesh::with_texture(texture.id());
+                mesh.add_rect_with_uv(
+                    Rect::from_min_max(
+                        range.min + tile.min.to_vec2() * scale,
+                        range.min + tile.max.to_vec2() * scale,
+                    ),
+                    FULL_UV,
+                    tint,
+                );
+                mesh.rotate(Rot2::from_angle(angle), range.center());
+                Shape::mesh(mesh)
+            })
+            .collect()
+    }
+
+    /// Paint the tiles which are visible through `view`, which maps image pixels to the screen.
+    pub fn paint(&self, painter: &Painter, view: &TSTransform) {
+        for (range, texture) in &self.tiles {
+            let render_range = to_screen_rect(range, view);
+            if painter.clip_rect().intersects(render_range) {
+                painter.image(texture.id(), render_range, FULL_UV, Color32::WHITE);
+            }
+        }
+    }
+}
@@ -1,38 +1,46 @@
-use eframe::egui::{Pos2, Rect, Vec2};
+use eframe::egui::{
+    Pos2, Rect,
+    emath::{Rot2, TSTransform},
+};
 
+/// The screen rect of `rect` given in image pixels.
 #[inline]
-pub fn to_ratio_rect(rect: &Rect, base: &Rect) -> Rect {
-    Rect {
-        min: to_ratio_pos(&rect.min, base),
-        max: to_ratio_pos(&rect.max, base),
-    }
+pub fn to_screen_rect(rect: &Rect, view: &TSTransform) -> Rect {
+    *view * *rect
 }
 
+/// The image pixel rect of `rect` given on the screen.
 #[inline]
-pub fn from_ratio_rect(rect: &Rect, base: &Rect) -> Rect {
-    Rect::from_min_max(
-        base.lerp_inside(rect.min.to_vec2()),
-        base.lerp_inside(rect.max.to_vec2()),
-    )
+pub fn to_image_rect(rect: &Rect, view: &TSTransform) -> Rect {
+    view.inverse() * *rect
 }
 
+/// The screen position of `pos` given in image pixels.
 #[inline]
-pub fn to_ratio_pos(pos: &Pos2, base: &Rect) -> Pos2 {
-    Pos2 {
-        x: (pos.x - base.min.x) / base.width(),
-        y: (pos.y - base.min.y) / base.height(),
-    }
+pub fn to_screen_pos(pos: &Pos2, view: &TSTransform) -> Pos2 {
+    *view * *pos
 }
 
+/// The image pixel position of `pos` given on the screen.
 #[inline]
-pub fn from_ratio_pos(pos: &Pos2, base: &Rect) -> Pos2 {
-    base.lerp_inside(pos.to_vec2())
+pub fn to_image_pos(pos: &Pos2, view: &TSTransform) -> Pos2 {
+    view.inverse() * *pos
 }
 
+/// The screen position of the image pixel corner nearest to `pos` given on the screen.
 #[inline]
-pub fn to_ratio_vec(pos: Vec2, base: &Rect) -> Vec2 {
-    Vec2 {
-        x: (pos.x - base.min.x) / base.width(),
-        y: (pos.y - base.min.y) / base.height(),
-    }
+pub fn snap_to_pixel(pos: &Pos2, view: &TSTransform) -> Pos2 {
+    to_screen_pos(&to_image_pos(pos, view).round(), view)
+}
+
+/// `rect` with its corners moved to the nearest pixel corners.
+#[inline]
+pub fn round_rect(rect: Rect) -> Rect {
+    Rect::from_min_max(rect.min.round(), rect.max.round())
+}
+
+/// Rotate `pos` by `angle` radians clockwise around `center`.
+#[inline]
+pub fn rotate_pos(pos: Pos2, center: Pos2, angle: f32) -> Pos2 {
+    center + Rot2::from_angle(angle) * (pos - center)
 }
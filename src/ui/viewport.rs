@@ -0,0 +1,111 @@
+use eframe::egui::{
+    Button, Key, Label, Modifiers, Pos2, Rect, Ui, Vec2, Widget, emath::TSTransform,
+};
+
+const MIN_ZOOM: f32 = 1f32 / 32f32;
+const MAX_ZOOM: f32 = 32f32;
+
+/// How the screenshot is panned and zoomed in the window.
+///
+/// The wheel pans, ctrl and the wheel or a pinch zoom around the cursor, and the middle button
+/// drags the screenshot. Ctrl+0 fits the screenshot into the window, ctrl+1 shows it at 100%.
+#[derive(Clone)]
+pub struct Viewport {
+    /// Physical screen pixels per image pixel.
+    pub zoom: f32,
+    /// The offset of the image origin from the top left of the window, in screen points.
+    pub pan: Vec2,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: 1f32,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+impl Viewport {
+    /// Pan and zoom with the pointer over the screenshot layer of `ui`, and with the shortcuts.
+    pub fn handle_input(&mut self, ui: &Ui, image_size: Vec2) {
+        let ctx = ui.ctx();
+        let window = ui.max_rect();
+        let (fit, actual_size) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::COMMAND, Key::Num0),
+                i.consume_key(Modifiers::COMMAND, Key::Num1),
+            )
+        });
+        if fit {
+            self.fit(window.size(), image_size, ctx.pixels_per_point());
+        }
+        if actual_size {
+            self.zoom_at(window.min, window.center(), 1f32, ctx.pixels_per_point());
+        }
+
+        let Some(pos) = ctx
+            .pointer_hover_pos()
+            .filter(|pos| ctx.layer_id_at(*pos) == Some(ui.layer_id()))
+        else {
+            return;
+        };
+        let (scroll, zoom_delta, middle_drag) = ctx.input(|i| {
+            (
+                i.smooth_scroll_delta,
+                i.zoom_delta(),
+                i.pointer.middle_down().then(|| i.pointer.delta()),
+            )
+        });
+        self.pan += scroll + middle_drag.unwrap_or(Vec2::ZERO);
+        if zoom_delta != 1f32 {
+            self.zoom_at(
+                window.min,
+                pos,
+                self.zoom * zoom_delta,
+                ctx.pixels_per_point(),
+            );
+        }
+    }
+
+    /// Maps image pixels to the screen, the image origin is kept on a physical pixel.
+    pub fn transform(&self, window_min: Pos2, pixels_per_point: f32) -> TSTransform {
+        let origin =
+            ((window_min.to_vec2() + self.pan) * pixels_per_point).round() / pixels_per_point;
+        TSTransform::new(origin, self.zoom / pixels_per_point)
+    }
+
+    /// Show the whole screenshot centered in the window.
+    pub fn fit(&mut self, window_size: Vec2, image_size: Vec2, pixels_per_point: f32) {
+        if image_size.min_elem() <= 0f32 {
+            return;
+        }
+        let fit = window_size * pixels_per_point / image_size;
+        self.zoom = fit.min_elem().clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = (window_size - image_size * self.zoom / pixels_per_point) / 2f32;
+    }
+
+    /// Zoom to `zoom` keeping the image pixel under `anchor` in place.
+    fn zoom_at(&mut self, window_min: Pos2, anchor: Pos2, zoom: f32, pixels_per_point: f32) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let origin = window_min + self.pan;
+        let image_pos = (anchor - origin) * pixels_per_point / self.zoom;
+        self.pan = anchor - image_pos * zoom / pixels_per_point - window_min;
+        self.zoom = zoom;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, window: Rect, image_size: Vec2) {
+        ui.horizontal(|ui| {
+            let pixels_per_point = ui.ctx().pixels_per_point();
+            if Button::new("Fit").ui(ui).clicked() {
+                self.fit(window.size(), image_size, pixels_per_point);
+            }
+            if Button::new("100%").ui(ui).clicked() {
+                self.zoom_at(window.min, window.center(), 1f32, pixels_per_point);
+            }
+            Label::new(format!("{:.0}%", self.zoom * 100f32))
+                .selectable(false)
+                .ui(ui);
+        });
+    }
+}
@@ -1,12 +1,15 @@
 use std::io::{Cursor, Write};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::mpsc::{Receiver, channel};
 use std::sync::{Arc, LazyLock};
 
+use eframe::egui::emath::TSTransform;
 use eframe::egui::mutex::Mutex;
-use eframe::egui::{self, Button, Image, Rect, Ui, Vec2, Widget, ahash::HashMap};
+use eframe::egui::{self, Button, Label, Rect, Ui, Vec2, Widget, ahash::HashMap};
 use eframe::egui::{
-    Align2, Color32, ColorImage, Context, FontFamily, Modifiers, PaintCallback, PaintCallbackInfo,
-    Response, RichText, Spinner,
+    Align2, Color32, ColorImage, Context, CursorIcon, FontFamily, FontId, Modifiers, PaintCallback,
+    PaintCallbackInfo, Pos2, ProgressBar, Response, RichText, Spinner, TextureOptions,
 };
 use eframe::egui_glow::Painter;
 use eframe::glow::{self, HasContext};
@@ -16,16 +19,28 @@ use strum::IntoEnumIterator;
 use strum::{EnumIter, IntoStaticStr};
 
 use crate::Arg;
+use crate::input::Screenshot;
+use crate::ui::checkerboard::Checkerboard;
+use crate::ui::color;
 use crate::ui::crop::CropTool;
+use crate::ui::loupe;
+use crate::ui::shape::callout::{Callout, CalloutAttribute};
 use crate::ui::shape::circle::{Circle, CircleAttribute};
 use crate::ui::shape::line::{Line, LineAttribute};
+use crate::ui::shape::measure::{Measure, MeasureAttribute};
 use crate::ui::shape::number::{Number, NumberAttribute};
 use crate::ui::shape::pen::{Pen, PenAttribute};
+use crate::ui::shape::picture::{self, Picture, PictureAttribute};
+use crate::ui::shape::protractor::{Protractor, ProtractorAttribute};
 use crate::ui::shape::rectangle::RectangleAttribute;
+use crate::ui::shape::stamp::{Stamp, StampAttribute};
 use crate::ui::shape::text::{Text, TextAttribute};
 use crate::ui::shape::{CreateAt, Shape};
 use crate::ui::shape::{ShapeId, rectangle::Rectangle};
-use crate::ui::utils::from_ratio_rect;
+use crate::ui::snap::{Grid, SnapTargets};
+use crate::ui::tiled::TiledTexture;
+use crate::ui::utils::{round_rect, to_screen_rect};
+use crate::ui::viewport::Viewport;
 use crate::ui::window::RenderInfo;
 
 #[derive(PartialEq, Eq, Clone, Copy, Default, EnumIter, IntoStaticStr)]
@@ -37,12 +52,24 @@ enum Tool {
     Circle,
     Line,
     Text,
+    Callout,
     Number,
     Pen,
+    Stamp,
+    Measure,
+    Angle,
+    Image,
 }
 
 pub struct EditWindow<'a> {
-    raw_screenshot_texture: egui::load::Bytes,
+    /// The screenshot being decoded in the background
+    screenshot_decoder: Option<Receiver<anyhow::Result<Screenshot>>>,
+    /// The decoded screenshot
+    screenshot_pixels: Option<ColorImage>,
+    screenshot_texture: Option<TiledTexture>,
+    /// Whether the screenshot has no transparent pixels
+    screenshot_opaque: bool,
+    checkerboard: Option<Checkerboard>,
 
     /// The currently selected tool in the toolbar
     selected_tool: Tool,
@@ -58,12 +85,29 @@ pub struct EditWindow<'a> {
     circle_attributes: CircleAttribute,
     line_attributes: LineAttribute,
     text_attributes: TextAttribute,
+    callout_attributes: CalloutAttribute,
     number_attributes: NumberAttribute,
     pen_attributes: PenAttribute,
+    stamp_attributes: StampAttribute,
+    measure_attributes: MeasureAttribute,
+    protractor_attributes: ProtractorAttribute,
+    picture_attributes: PictureAttribute,
+
+    /// The running file chooser of the image tool
+    file_chooser: Option<Receiver<Result<Option<PathBuf>, String>>>,
+
+    grid: Grid,
+    viewport: Viewport,
 
     error_message: Option<String>,
 
     want_screenshot: bool,
+    /// A transparent screenshot is read back over black first and then over white
+    shot_over_black: Option<ColorImage>,
+    /// The running export of a crop which does not fit on the screen
+    tiled_export: Option<TiledExport>,
+    /// The running encoding and writing of the screenshot
+    saving: Option<Receiver<Result<(), String>>>,
     screenshot_copy: bool,
     screenshot_save: bool,
     screenshot_pin: bool,
@@ -75,26 +119,41 @@ pub struct EditWindow<'a> {
 
 impl<'a> EditWindow<'a> {
     pub fn new(
-        raw_screenshot: Vec<u8>,
+        screenshot: Receiver<anyhow::Result<Screenshot>>,
         user_font: FontFamily,
         arg: Arg,
         pinned_image: &'a mut Option<ColorImage>,
     ) -> Self {
         Self {
-            raw_screenshot_texture: egui::load::Bytes::from(raw_screenshot),
+            screenshot_decoder: Some(screenshot),
+            screenshot_pixels: None,
+            screenshot_texture: None,
+            screenshot_opaque: true,
+            checkerboard: None,
             selected_tool: Default::default(),
-            crop_tool: Default::default(),
+            crop_tool: CropTool::new(arg.crop, arg.crop_ratio),
             shapes: Default::default(),
             active_shape_id: None,
             rect_attributes: Default::default(),
             circle_attributes: Default::default(),
             line_attributes: Default::default(),
             text_attributes: Default::default(),
+            callout_attributes: Default::default(),
             number_attributes: Default::default(),
             pen_attributes: Default::default(),
+            stamp_attributes: Default::default(),
+            measure_attributes: Default::default(),
+            protractor_attributes: Default::default(),
+            picture_attributes: Default::default(),
+            file_chooser: None,
+            grid: Default::default(),
+            viewport: Default::default(),
             error_message: None,
             user_font,
             want_screenshot: false,
+            shot_over_black: None,
+            tiled_export: None,
+            saving: None,
             screenshot_copy: false,
             screenshot_save: false,
             screenshot_pin: false,
@@ -111,44 +170,71 @@ impl<'a> EditWindow<'a> {
 impl<'a> App for EditWindow<'a> {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let mut render_info = RenderInfo {
+            view: TSTransform::IDENTITY,
             screenshot_rect: Rect::ZERO,
             pixel_ratio: 1f32,
             user_font: self.user_font.clone(),
-            shot_mode: self.want_screenshot,
+            shot_mode: self.want_screenshot || self.tiled_export.is_some(),
+            snap_targets: Default::default(),
         };
+        // escape stops the eyedropper instead of closing the window
+        let stop_eyedropper = color::eyedropper_active(ctx)
+            && ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape));
+        if stop_eyedropper || (!ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(Key::I)))
+        {
+            color::toggle_eyedropper(ctx);
+        }
         egui::CentralPanel::default()
             .frame(egui::containers::Frame::NONE)
             .show(ctx, |ui| {
                 let resp = self.ui_background(ui, &mut render_info);
+                render_info.snap_targets = self.snap_targets(ui, &render_info);
 
                 // render all shapes
                 self.ui_shape(ui, &render_info);
 
                 // handle global mouse event
-                self.handle_global_response(ui, &resp, &render_info);
+                if color::eyedropper_active(ctx) && !render_info.shot_mode {
+                    self.ui_eyedropper(ui, &render_info);
+                } else {
+                    self.handle_global_response(ui, &resp, &render_info);
+                }
 
                 // render crop range
-                if !self.want_screenshot {
+                if !render_info.shot_mode {
                     self.crop_tool
                         .ui(ui, &render_info, self.selected_tool == Tool::Crop);
+                    self.ui_loupe(ui, &render_info);
                 }
 
-                if self.want_screenshot {
+                let shot_range = match &mut self.tiled_export {
+                    Some(export) if !export.reading => {
+                        export.reading = true;
+                        Some(to_screen_rect(&export.tile, &render_info.view))
+                    }
+                    Some(_) => None,
+                    None => self
+                        .want_screenshot
+                        .then(|| self.crop_tool.render_range(&render_info)),
+                };
+                if let Some(rect) = shot_range {
                     ui.painter().add(egui::Shape::Callback(PaintCallback {
-                        rect: from_ratio_rect(
-                            &self.crop_tool.cropped_range,
-                            &render_info.screenshot_rect,
-                        ),
+                        rect,
                         callback: Arc::<egui_glow::CallbackFn>::new(egui_glow::CallbackFn::new(
                             screenshot,
                         )),
                     }));
                     self.want_screenshot = false;
                 }
+                if self.tiled_export.is_some() {
+                    ctx.request_repaint();
+                }
                 // Check for returned screenshot
                 self.handle_screenshot_event(ctx);
+                self.handle_save_result(ctx);
             });
         self.ui_toolbar(ctx, &render_info);
+        self.handle_picture_input(ctx, &render_info);
 
         self.ui_error_message(ctx);
     }
@@ -156,32 +242,108 @@ impl<'a> App for EditWindow<'a> {
 
 impl<'a> EditWindow<'a> {
     fn ui_background(&mut self, ui: &mut Ui, render_info: &mut RenderInfo) -> Response {
-        let screenshot_image =
-            Image::from_bytes("bytes://picture", self.raw_screenshot_texture.clone())
-                .fit_to_original_size(1f32 / ui.ctx().pixels_per_point())
-                .show_loading_spinner(false)
-                .sense(egui::Sense::click_and_drag());
-        let image_size = screenshot_image
-            .load_for_size(ui.ctx(), Vec2::INFINITY)
-            .ok()
-            .and_then(|t| t.size());
-        let resp = screenshot_image.ui(ui);
-
-        // render spinner if image is not load
-        if image_size.is_none() {
+        if let Some(decoder) = &self.screenshot_decoder
+            && let Ok(result) = decoder.try_recv()
+        {
+            self.screenshot_decoder = None;
+            match result {
+                Ok(Screenshot { image, opaque }) => {
+                    self.screenshot_opaque = opaque;
+                    let image_size = Vec2::new(image.width() as f32, image.height() as f32);
+                    if let Err(e) = self.crop_tool.clamp_to_image(image_size) {
+                        self.error_message = Some(e);
+                    }
+                    self.screenshot_pixels = Some(image);
+                }
+                Err(e) => {
+                    self.error_message =
+                        Some(format!("Failed to decode the screenshot. Reason: {e}"))
+                }
+            }
+        }
+        if self.screenshot_texture.is_none()
+            && let Some(image) = &self.screenshot_pixels
+        {
+            self.screenshot_texture = Some(TiledTexture::new(
+                ui.ctx(),
+                "screenshot",
+                image,
+                TextureOptions::LINEAR,
+            ));
+        }
+        let image_size = self
+            .screenshot_texture
+            .as_ref()
+            .map_or(Vec2::ZERO, |texture| texture.size());
+
+        // a tiled export shows the tile being read back at one image pixel per screen pixel
+        let view = if let Some(export) = &self.tiled_export {
+            let pixel_ratio = 1f32 / ui.ctx().pixels_per_point();
+            TSTransform::new(
+                ui.max_rect().min.to_vec2() - export.tile.min.to_vec2() * pixel_ratio,
+                pixel_ratio,
+            )
+        } else {
+            if !render_info.shot_mode {
+                self.viewport.handle_input(ui, image_size);
+            }
+            self.viewport
+                .transform(ui.max_rect().min, ui.ctx().pixels_per_point())
+        };
+        let pixel_ratio = view.scaling;
+        let screenshot_rect = to_screen_rect(&Rect::from_min_size(Pos2::ZERO, image_size), &view);
+        let resp = ui.allocate_rect(screenshot_rect, egui::Sense::click_and_drag());
+        if !self.screenshot_opaque {
+            if !render_info.shot_mode {
+                self.checkerboard
+                    .get_or_insert_with(|| Checkerboard::new(ui.ctx()))
+                    .paint(ui.painter(), screenshot_rect);
+            } else if self.shot_over_black.is_some() {
+                ui.painter()
+                    .rect_filled(screenshot_rect, 0f32, Color32::WHITE);
+            } else {
+                ui.painter()
+                    .rect_filled(screenshot_rect, 0f32, Color32::BLACK);
+            }
+        }
+        if let Some(texture) = &self.screenshot_texture {
+            texture.paint(ui.painter(), &view);
+        }
+
+        // render spinner if image is not decoded yet
+        if self.screenshot_decoder.is_some() {
             Spinner::new().paint_at(
                 ui,
                 Rect::from_center_size(ui.max_rect().center(), Vec2::splat(70f32)),
             );
+            ui.ctx().request_repaint();
         }
 
-        let pixel_ratio = image_size.map_or(1f32, |v| resp.rect.width() / v.x);
-        let screenshot_rect = resp.rect;
+        render_info.view = view;
         render_info.screenshot_rect = screenshot_rect;
         render_info.pixel_ratio = pixel_ratio;
+
+        if !render_info.shot_mode {
+            self.grid.paint(ui.painter(), screenshot_rect, pixel_ratio);
+        }
         resp
     }
 
+    /// The image bounds, the crop rect and every shape but the active one.
+    fn snap_targets(&self, ui: &Ui, render_info: &RenderInfo) -> SnapTargets {
+        let mut targets = SnapTargets::new(render_info.screenshot_rect);
+        targets.set_grid(&self.grid, render_info.pixel_ratio);
+        if self.selected_tool != Tool::Crop {
+            targets.add_rect(self.crop_tool.render_range(render_info));
+        }
+        for (shape_id, shape) in &self.shapes {
+            if self.active_shape_id != Some(*shape_id) {
+                targets.add_rect(shape.bounding_rect(ui, render_info));
+            }
+        }
+        targets
+    }
+
     fn ui_toolbar(&mut self, ctx: &Context, render_info: &RenderInfo) {
         egui::Window::new("Tools")
             .collapsible(false)
@@ -206,50 +368,111 @@ impl<'a> EditWindow<'a> {
                     }
                 });
 
+                if color::eyedropper_active(ctx) {
+                    ui.separator();
+                    color::eyedropper_ui(ui);
+                }
+
                 ui.separator();
 
-                // save, copy and pin
-                let cropped_range =
-                    from_ratio_rect(&self.crop_tool.cropped_range, &render_info.screenshot_rect);
-                let saveable = ctx.available_rect().contains_rect(cropped_range);
+                // save, copy and pin, one at a time
+                let idle =
+                    !self.want_screenshot && self.tiled_export.is_none() && self.saving.is_none();
                 ui.horizontal(|ui| {
-                    let save_shotcut = ctx.input_mut(|i| i.consume_key(Modifiers::CTRL, Key::S));
-                    let save_btn_clicked = ui.add_enabled(saveable, Button::new("Save")).clicked();
+                    let save_shotcut =
+                        ctx.input_mut(|i| i.consume_key(Modifiers::CTRL, Key::S)) && idle;
+                    let save_btn_clicked = ui.add_enabled(idle, Button::new("Save")).clicked();
                     let copy_shotcut = ui
                         .input(|inp| inp.events.iter().any(|ev| matches!(ev, egui::Event::Copy)))
-                        && !ctx.wants_keyboard_input();
-                    let copy_btn_clicked = ui.add_enabled(saveable, Button::new("Copy")).clicked();
-                    let copy_and_save_btn_clicked = ui.add_enabled(saveable, Button::new("Copy and Save")).clicked();
-                    let pin_btn_clicked = ui.add_enabled(saveable, Button::new("Pin")).clicked();
-
-                    if save_shotcut || save_btn_clicked || copy_shotcut || copy_btn_clicked || copy_and_save_btn_clicked || pin_btn_clicked {
-                        if !saveable {
-                            self.error_message = Some("Save or copy failed. Crop area exceeds screen. Please maximize the window and try again.".to_string());
-                        } else {
-                            self.want_screenshot = true;
-                            self.screenshot_copy = copy_shotcut || copy_btn_clicked || copy_and_save_btn_clicked;
-                            self.screenshot_save = save_shotcut || save_btn_clicked || copy_and_save_btn_clicked;
-                            self.screenshot_pin = pin_btn_clicked;
-                        }
+                        && !ctx.wants_keyboard_input()
+                        && idle;
+                    let copy_btn_clicked = ui.add_enabled(idle, Button::new("Copy")).clicked();
+                    let copy_and_save_btn_clicked =
+                        ui.add_enabled(idle, Button::new("Copy and Save")).clicked();
+                    let pin_btn_clicked = ui.add_enabled(idle, Button::new("Pin")).clicked();
+
+                    if save_shotcut
+                        || save_btn_clicked
+                        || copy_shotcut
+                        || copy_btn_clicked
+                        || copy_and_save_btn_clicked
+                        || pin_btn_clicked
+                    {
+                        self.start_screenshot(ctx, render_info);
+                        self.screenshot_copy =
+                            copy_shotcut || copy_btn_clicked || copy_and_save_btn_clicked;
+                        self.screenshot_save =
+                            save_shotcut || save_btn_clicked || copy_and_save_btn_clicked;
+                        self.screenshot_pin = pin_btn_clicked;
                     }
                 });
+                if let Some(export) = &self.tiled_export {
+                    ProgressBar::new(export.progress())
+                        .text("Rendering…")
+                        .ui(ui);
+                } else if self.saving.is_some() {
+                    ui.horizontal(|ui| {
+                        Spinner::new().ui(ui);
+                        Label::new("Saving…").selectable(false).ui(ui);
+                    });
+                }
+                let image_size = self
+                    .screenshot_texture
+                    .as_ref()
+                    .map_or(Vec2::ZERO, |texture| texture.size());
+                self.viewport.ui(ui, ctx.screen_rect(), image_size);
+                self.grid.ui(ui);
                 ui.separator();
                 egui::Grid::new("attributes").show(ui, |ui| {
                     if let Some(active_shape) = self.active_shape() {
                         active_shape.toolbar_ui(ui, render_info);
                     } else {
                         match self.selected_tool {
-                            Tool::None | Tool::Crop => {}
+                            Tool::None => {}
+                            Tool::Crop => self.crop_tool.toolbar_ui(ui),
                             Tool::Circle => self.circle_attributes.ui(ui),
                             Tool::Rect => self.rect_attributes.ui(ui),
                             Tool::Line => self.line_attributes.ui(ui),
                             Tool::Text => {
                                 self.text_attributes.ui(ui, render_info.user_font.clone())
                             }
+                            Tool::Callout => self
+                                .callout_attributes
+                                .ui(ui, render_info.user_font.clone()),
                             Tool::Number => self.number_attributes.ui(ui),
                             Tool::Pen => self.pen_attributes.ui(ui),
+                            Tool::Stamp => self.stamp_attributes.ui(ui),
+                            Tool::Measure => self.measure_attributes.ui(ui),
+                            Tool::Angle => self.protractor_attributes.ui(ui),
+                            Tool::Image => {
+                                Label::new("Insert").selectable(false).ui(ui);
+                                ui.horizontal(|ui| {
+                                    let open_btn = Button::new("Open…");
+                                    if ui
+                                        .add_enabled(self.file_chooser.is_none(), open_btn)
+                                        .clicked()
+                                    {
+                                        self.file_chooser = Some(picture::choose_file(ctx));
+                                    }
+                                    if ui.button("Paste").clicked() {
+                                        match picture::load_from_clipboard() {
+                                            Ok(image) => {
+                                                self.insert_picture(ctx, image, render_info)
+                                            }
+                                            Err(e) => {
+                                                self.error_message = Some(format!(
+                                                    "No image in the clipboard. Reason: {e}"
+                                                ))
+                                            }
+                                        }
+                                    }
+                                });
+                                ui.end_row();
+                                self.picture_attributes.ui(ui);
+                            }
                         }
                     }
+                    color::palette_ui(ui);
                 });
             });
     }
@@ -259,7 +482,7 @@ impl<'a> EditWindow<'a> {
         for (shape_id, shape) in self.shapes.iter_mut() {
             if shape.ui(
                 ui,
-                !self.want_screenshot && self.active_shape_id.is_some_and(|x| &x == shape_id),
+                !render_info.shot_mode && self.active_shape_id.is_some_and(|x| &x == shape_id),
                 render_info,
             ) {
                 has_active = true;
@@ -328,6 +551,14 @@ impl<'a> EditWindow<'a> {
                 &mut self.active_shape_id,
                 &mut self.shapes,
             ),
+            Tool::Callout => Callout::handle_create_response(
+                ui,
+                resp,
+                render_info,
+                &self.callout_attributes,
+                &mut self.active_shape_id,
+                &mut self.shapes,
+            ),
             Tool::Number => Number::handle_create_response(
                 ui,
                 resp,
@@ -344,29 +575,273 @@ impl<'a> EditWindow<'a> {
                 &mut self.active_shape_id,
                 &mut self.shapes,
             ),
+            Tool::Stamp => Stamp::handle_create_response(
+                ui,
+                resp,
+                render_info,
+                &self.stamp_attributes,
+                &mut self.active_shape_id,
+                &mut self.shapes,
+            ),
+            Tool::Measure => Measure::handle_create_response(
+                ui,
+                resp,
+                render_info,
+                &self.measure_attributes,
+                &mut self.active_shape_id,
+                &mut self.shapes,
+            ),
+            Tool::Angle => Protractor::handle_create_response(
+                ui,
+                resp,
+                render_info,
+                &self.protractor_attributes,
+                &mut self.active_shape_id,
+                &mut self.shapes,
+            ),
+            Tool::Image => {
+                if resp.clicked() {
+                    self.active_shape_id = None;
+                }
+            }
             Tool::None => {}
         }
     }
 
-    fn handle_screenshot_event(&mut self, ctx: &Context) {
-        let Some(cropped) = SAVED_IMAGE.lock().take() else {
+    /// Sample the screenshot under the cursor and hand the color to the picker being edited.
+    fn ui_eyedropper(&mut self, ui: &mut Ui, render_info: &RenderInfo) {
+        let Some(pos) = ui.ctx().pointer_hover_pos() else {
             return;
         };
-        if let Err(e) = save_image_as_file(
-            cropped.clone(),
-            self.screenshot_copy,
-            self.screenshot_save.then_some(&self.arg.output),
-            ctx,
-        ) {
-            self.error_message = Some(e.to_string());
+        if ui.ctx().layer_id_at(pos) != Some(ui.layer_id()) {
             return;
         }
-        if self.arg.exit && !self.screenshot_pin {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        let Some(color) = self.screenshot_pixel(ui.ctx(), pos, render_info) else {
+            return;
+        };
+        ui.ctx().set_cursor_icon(CursorIcon::Crosshair);
+
+        // show the color and its value next to the cursor
+        let painter = ui.painter();
+        let galley =
+            painter.layout_no_wrap(color::hex(color), FontId::monospace(12f32), Color32::WHITE);
+        let swatch_size = Vec2::splat(galley.size().y);
+        let preview = Rect::from_min_size(
+            pos + Vec2::splat(16f32),
+            Vec2::new(swatch_size.x + galley.size().x + 4f32, swatch_size.y) + Vec2::splat(8f32),
+        );
+        painter.rect_filled(preview, 4f32, Color32::from_black_alpha(200));
+        let swatch = Rect::from_min_size(preview.min + Vec2::splat(4f32), swatch_size);
+        painter.rect_filled(swatch, 2f32, color);
+        painter.galley(
+            swatch.right_top() + Vec2::new(4f32, 0f32),
+            galley,
+            Color32::WHITE,
+        );
+
+        if ui.input(|i| i.pointer.primary_clicked()) {
+            color::eyedropper_pick(ui.ctx(), color);
+        }
+    }
+
+    /// Magnify the pixels under the cursor while a shape is created or a handle is dragged.
+    fn ui_loupe(&mut self, ui: &Ui, render_info: &RenderInfo) {
+        let ctx = ui.ctx().clone();
+        let dragging = ctx.dragged_id().is_some()
+            && ctx
+                .input(|i| i.pointer.press_origin())
+                .is_some_and(|pos| ctx.layer_id_at(pos) == Some(ui.layer_id()));
+        if !dragging {
+            return;
+        }
+        let Some(pos) = ctx.pointer_latest_pos() else {
+            return;
+        };
+        if let Some(image) = &self.screenshot_pixels {
+            loupe::loupe_ui(&ctx, image, pos, render_info);
+        }
+    }
+
+    /// The pixel of the screenshot at `pos`.
+    fn screenshot_pixel(
+        &mut self,
+        ctx: &Context,
+        pos: Pos2,
+        render_info: &RenderInfo,
+    ) -> Option<Color32> {
+        let Some(image) = &self.screenshot_pixels else {
+            color::toggle_eyedropper(ctx);
+            return None;
+        };
+        let (x, y) = loupe::image_pixel(pos, render_info);
+        (x >= 0 && y >= 0 && (x as usize) < image.width() && (y as usize) < image.height())
+            .then(|| image[(x as usize, y as usize)])
+    }
+
+    fn insert_picture(&mut self, ctx: &Context, image: ColorImage, render_info: &RenderInfo) {
+        let shape_id = ShapeId::new();
+        self.shapes.insert(
+            shape_id,
+            Picture::insert(ctx, image, self.picture_attributes.clone(), render_info),
+        );
+        self.active_shape_id = Some(shape_id);
+    }
+
+    /// Insert pictures from the file chooser, dropped files and the clipboard.
+    fn handle_picture_input(&mut self, ctx: &Context, render_info: &RenderInfo) {
+        let mut paths = Vec::new();
+        if let Some(file_chooser) = &self.file_chooser
+            && let Ok(result) = file_chooser.try_recv()
+        {
+            self.file_chooser = None;
+            match result {
+                Ok(path) => paths.extend(path),
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+
+        let (dropped_files, paste_key, pasted_text) = ctx.input(|i| {
+            let pasted_text = i.events.iter().find_map(|ev| match ev {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            });
+            (
+                i.raw.dropped_files.clone(),
+                i.key_released(Key::V) && i.modifiers.command,
+                pasted_text,
+            )
+        });
+        for file in dropped_files {
+            let image = match (file.path, file.bytes) {
+                (Some(path), _) => picture::load_from_file(&path),
+                (None, Some(bytes)) => picture::load_from_memory(&bytes),
+                (None, None) => continue,
+            };
+            match image {
+                Ok(image) => self.insert_picture(ctx, image, render_info),
+                Err(e) => self.error_message = Some(format!("Failed to insert image. Reason: {e}")),
+            }
+        }
+
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // a copied file is pasted as its path
+        if let Some(text) = pasted_text {
+            let text = text.trim();
+            let path = PathBuf::from(text.strip_prefix("file://").unwrap_or(text));
+            if path.is_file() {
+                paths.push(path);
+            }
+        } else if paste_key && let Ok(image) = picture::load_from_clipboard() {
+            // egui only reports the release of ctrl+v if the clipboard holds no text
+            self.insert_picture(ctx, image, render_info);
+        }
+
+        for path in paths {
+            match picture::load_from_file(&path) {
+                Ok(image) => self.insert_picture(ctx, image, render_info),
+                Err(e) => {
+                    self.error_message = Some(format!("Failed to load image {path:?}. Reason: {e}"))
+                }
+            }
+        }
+    }
+
+    /// Read the crop back in the next frame, tile by tile if it does not fit on the screen or the
+    /// view is zoomed.
+    fn start_screenshot(&mut self, ctx: &Context, render_info: &RenderInfo) {
+        let image_size = self
+            .screenshot_texture
+            .as_ref()
+            .map_or(Vec2::ZERO, |texture| texture.size());
+        let range = self
+            .crop_tool
+            .cropped_range
+            .intersect(Rect::from_min_size(Pos2::ZERO, image_size));
+        if !range.is_positive() {
+            self.error_message =
+                Some("Nothing to save, the crop is outside of the screenshot.".to_string());
+            return;
+        }
+        if self.viewport.zoom == 1f32
+            && ctx
+                .available_rect()
+                .contains_rect(self.crop_tool.render_range(render_info))
+        {
+            self.want_screenshot = true;
+            return;
         }
+        // the tiles are rendered at one image pixel per screen pixel
+        let tile_size = (ctx.available_rect().size() * ctx.pixels_per_point()).floor();
+        self.tiled_export = Some(TiledExport::new(range, tile_size));
+    }
+
+    fn handle_screenshot_event(&mut self, ctx: &Context) {
+        let Some(shot) = SAVED_IMAGE.lock().take() else {
+            return;
+        };
+        let shot = if self.screenshot_opaque {
+            with_opaque_alpha(shot)
+        } else if let Some(over_black) = self.shot_over_black.take() {
+            unmatte(&over_black, &shot)
+        } else {
+            // read the same range again over white
+            self.shot_over_black = Some(shot);
+            match &mut self.tiled_export {
+                Some(export) => export.reading = false,
+                None => self.want_screenshot = true,
+            }
+            ctx.request_repaint();
+            return;
+        };
+        let mut cropped = match &mut self.tiled_export {
+            Some(export) => {
+                if !export.add_tile(&shot) {
+                    ctx.request_repaint();
+                    return;
+                }
+                self.tiled_export.take().unwrap().image
+            }
+            None => shot,
+        };
+        let image_size = self
+            .screenshot_texture
+            .as_ref()
+            .map_or(Vec2::ZERO, |texture| texture.size());
+        let origin = round_rect(
+            self.crop_tool
+                .cropped_range
+                .intersect(Rect::from_min_size(Pos2::ZERO, image_size)),
+        )
+        .min;
+        self.crop_tool.apply_mask(&mut cropped, origin);
         if self.screenshot_pin {
             *self.pinned_image = Some(cropped);
             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+        self.saving = Some(save_in_background(
+            cropped,
+            self.screenshot_copy,
+            self.screenshot_save.then(|| self.arg.output.clone()),
+            ctx,
+        ));
+    }
+
+    /// Report the end of the running save, and exit after a successful one if asked to.
+    fn handle_save_result(&mut self, ctx: &Context) {
+        let Some(saving) = &self.saving else {
+            return;
+        };
+        let Ok(result) = saving.try_recv() else {
+            return;
+        };
+        self.saving = None;
+        match result {
+            Ok(()) if self.arg.exit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            Ok(()) => {}
+            Err(e) => self.error_message = Some(e),
         }
     }
 
@@ -394,6 +869,24 @@ impl<'a> EditWindow<'a> {
     }
 }
 
+/// Encode, copy and write the image on a worker thread, the result is sent once it is done.
+fn save_in_background(
+    image: ColorImage,
+    copy: bool,
+    output_path: Option<String>,
+    ctx: &Context,
+) -> Receiver<Result<(), String>> {
+    let (sender, receiver) = channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let result = save_image_as_file(image, copy, output_path.as_deref(), &ctx)
+            .map_err(|e| format!("Failed to save the screenshot. Reason: {e}"));
+        let _ = sender.send(result);
+        ctx.request_repaint();
+    });
+    receiver
+}
+
 fn save_image_as_file(
     image: ColorImage,
     copy: bool,
@@ -401,9 +894,14 @@ fn save_image_as_file(
     ctx: &Context,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut cursor = Cursor::new(Vec::new());
+    let rgba: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|p| p.to_srgba_unmultiplied())
+        .collect();
     image::write_buffer_with_format(
         &mut cursor,
-        image.as_raw(),
+        &rgba,
         image.width() as u32,
         image.height() as u32,
         image::ColorType::Rgba8,
@@ -435,6 +933,99 @@ fn save_image_as_file(
     Ok(())
 }
 
+/// A crop which does not fit on the screen, it is rendered and read back one tile per frame.
+struct TiledExport {
+    /// The crop in image pixels
+    range: Rect,
+    /// The tile being rendered in image pixels
+    tile: Rect,
+    tile_size: Vec2,
+    /// Whether the tile is being read back
+    reading: bool,
+    image: ColorImage,
+}
+
+impl TiledExport {
+    fn new(range: Rect, tile_size: Vec2) -> Self {
+        // whole pixels, so that the tiles cover every row and column of the image
+        let range = round_rect(range);
+        let tile_size = tile_size.round().max(Vec2::splat(1f32));
+        Self {
+            range,
+            tile: Rect::from_min_size(range.min, tile_size).intersect(range),
+            tile_size,
+            reading: false,
+            image: ColorImage::filled(
+                [range.width() as usize, range.height() as usize],
+                Color32::TRANSPARENT,
+            ),
+        }
+    }
+
+    /// The share of the crop which is read back.
+    fn progress(&self) -> f32 {
+        let done_rows = (self.tile.top() - self.range.top()) * self.range.width();
+        let done_tiles = (self.tile.left() - self.range.left()) * self.tile.height();
+        (done_rows + done_tiles) / self.range.area().max(1f32)
+    }
+
+    /// Copy the tile which was read back into the image and go on with the next one, return
+    /// `true` once the last tile is done.
+    fn add_tile(&mut self, shot: &ColorImage) -> bool {
+        let left = (self.tile.left() - self.range.left()) as usize;
+        let top = (self.tile.top() - self.range.top()) as usize;
+        let width = shot.width().min(self.image.width() - left);
+        let height = shot.height().min(self.image.height() - top);
+        for y in 0..height {
+            let row = (top + y) * self.image.width() + left;
+            self.image.pixels[row..row + width]
+                .copy_from_slice(&shot.pixels[y * shot.width()..y * shot.width() + width]);
+        }
+        self.reading = false;
+
+        // row by row, from left to right
+        let next = if self.tile.right() < self.range.right() {
+            self.tile.right_top()
+        } else if self.tile.bottom() < self.range.bottom() {
+            Pos2::new(self.range.left(), self.tile.bottom())
+        } else {
+            return true;
+        };
+        self.tile = Rect::from_min_size(next, self.tile_size).intersect(self.range);
+        false
+    }
+}
+
+/// The framebuffer alpha is meaningless, the screenshot covers the whole read back range.
+fn with_opaque_alpha(mut image: ColorImage) -> ColorImage {
+    for pixel in &mut image.pixels {
+        *pixel = pixel.to_opaque();
+    }
+    image
+}
+
+/// Recover the colors and alpha of a range read back once over black and once over white. Over
+/// black the colors are premultiplied by alpha, and over white `255 * (1 - alpha)` is added.
+fn unmatte(over_black: &ColorImage, over_white: &ColorImage) -> ColorImage {
+    let pixels = over_black
+        .pixels
+        .iter()
+        .zip(&over_white.pixels)
+        .map(|(black, white)| {
+            let difference = (white.r() as u32 + white.g() as u32 + white.b() as u32)
+                .saturating_sub(black.r() as u32 + black.g() as u32 + black.b() as u32);
+            let alpha = 255 - (difference / 3).min(255) as u8;
+            Color32::from_rgba_premultiplied(
+                black.r().min(alpha),
+                black.g().min(alpha),
+                black.b().min(alpha),
+                alpha,
+            )
+        })
+        .collect();
+    ColorImage::new(over_black.size, pixels)
+}
+
 static SAVED_IMAGE: LazyLock<Mutex<Option<egui::ColorImage>>> = LazyLock::new(|| Mutex::new(None));
 
 fn screenshot(callback_info: PaintCallbackInfo, painter: &Painter) {
@@ -460,3 +1051,126 @@ fn screenshot(callback_info: PaintCallbackInfo, painter: &Painter) {
         flipped,
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(color: Color32) -> ColorImage {
+        ColorImage::new([1, 1], vec![color])
+    }
+
+    fn unmatte_pixel(over_black: Color32, over_white: Color32) -> Color32 {
+        unmatte(&pixel(over_black), &pixel(over_white)).pixels[0]
+    }
+
+    #[test]
+    fn unmatte_opaque() {
+        for color in [
+            Color32::from_rgb(12, 34, 56),
+            Color32::BLACK,
+            Color32::WHITE,
+        ] {
+            assert_eq!(unmatte_pixel(color, color), color);
+        }
+    }
+
+    #[test]
+    fn unmatte_transparent() {
+        assert_eq!(
+            unmatte_pixel(Color32::BLACK, Color32::WHITE),
+            Color32::TRANSPARENT
+        );
+    }
+
+    #[test]
+    fn unmatte_half_transparent() {
+        // red at half alpha adds half of the white below it
+        let color = unmatte_pixel(
+            Color32::from_rgb(128, 0, 0),
+            Color32::from_rgb(255, 127, 127),
+        );
+        assert_eq!(color, Color32::from_rgba_premultiplied(128, 0, 0, 128));
+        assert_eq!(color.to_srgba_unmultiplied(), [255, 0, 0, 128]);
+
+        // gray at a quarter alpha
+        let color = unmatte_pixel(Color32::from_gray(32), Color32::from_gray(32 + 191));
+        assert_eq!(color, Color32::from_rgba_premultiplied(32, 32, 32, 64));
+    }
+
+    #[test]
+    fn unmatte_clamps() {
+        // the white sample darker than the black one counts as opaque
+        let black = Color32::from_rgb(200, 150, 100);
+        assert_eq!(unmatte_pixel(black, Color32::from_rgb(100, 50, 0)), black);
+        // a premultiplied color never exceeds its alpha
+        let color = unmatte_pixel(Color32::from_rgb(200, 0, 0), Color32::WHITE);
+        assert_eq!(color.a(), 67);
+        assert_eq!(color.r(), 67);
+    }
+
+    /// Export `range` tile by tile, every pixel of a tile holds the number of the tile.
+    fn export_tiles(range: Rect, tile_size: Vec2) -> (ColorImage, Vec<f32>) {
+        let mut export = TiledExport::new(range, tile_size);
+        let mut progress = Vec::new();
+        for i in 0u8.. {
+            progress.push(export.progress());
+            let size = export.tile.size();
+            let shot =
+                ColorImage::filled([size.x as usize, size.y as usize], Color32::from_gray(i));
+            if export.add_tile(&shot) {
+                break;
+            }
+        }
+        (export.image, progress)
+    }
+
+    #[test]
+    fn tiled_export_with_partial_tiles() {
+        let range = Rect::from_min_size(Pos2::new(10f32, 20f32), Vec2::new(250f32, 130f32));
+        let (image, progress) = export_tiles(range, Vec2::splat(100f32));
+        assert_eq!(image.size, [250, 130]);
+        // three tiles per row, the last ones are cut off
+        assert_eq!(progress.len(), 6);
+        assert_eq!(progress[0], 0f32);
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(progress[5] < 1f32);
+        let at = |x: usize, y: usize| image.pixels[y * 250 + x].r();
+        assert_eq!(at(0, 0), 0);
+        assert_eq!(at(99, 99), 0);
+        assert_eq!(at(100, 0), 1);
+        assert_eq!(at(249, 99), 2);
+        assert_eq!(at(0, 100), 3);
+        assert_eq!(at(150, 129), 4);
+        assert_eq!(at(249, 129), 5);
+    }
+
+    #[test]
+    fn tiled_export_of_a_fractional_crop() {
+        let range = Rect::from_min_max(Pos2::new(10.4f32, 20.6f32), Pos2::new(260.2f32, 149.7f32));
+        let (image, progress) = export_tiles(range, Vec2::new(100.4f32, 99.6f32));
+        assert_eq!(image.size, [250, 129]);
+        assert_eq!(progress.len(), 6);
+        // every pixel is written, the last column and row too
+        assert!(image.pixels.iter().all(|pixel| pixel.a() == 255));
+        assert_eq!(image.pixels[128 * 250 + 249].r(), 5);
+    }
+
+    #[test]
+    fn tiled_export_with_a_larger_read_back() {
+        // a tile which is read back larger than the crop is cut to it
+        let mut export = TiledExport::new(
+            Rect::from_min_size(Pos2::ZERO, Vec2::new(30f32, 20f32)),
+            Vec2::splat(50f32),
+        );
+        assert!(export.add_tile(&ColorImage::filled([50, 50], Color32::WHITE)));
+        assert_eq!(export.image.size, [30, 20]);
+        assert!(
+            export
+                .image
+                .pixels
+                .iter()
+                .all(|pixel| *pixel == Color32::WHITE)
+        );
+    }
+}
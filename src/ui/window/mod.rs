@@ -1,11 +1,18 @@
-use eframe::egui::{FontFamily, Rect};
+use eframe::egui::{FontFamily, Rect, emath::TSTransform};
+
+use crate::ui::snap::SnapTargets;
 
 pub mod edit_window;
 pub mod pin_window;
 
 pub struct RenderInfo {
+    /// Maps image pixels, which shapes and the crop are stored in, to the screen.
+    pub view: TSTransform,
+    /// The screenshot on the screen, the view applied to the image bounds.
     pub screenshot_rect: Rect,
+    /// Screen points per image pixel, the scaling of the view.
     pub pixel_ratio: f32,
     pub user_font: FontFamily,
     pub shot_mode: bool,
+    pub snap_targets: SnapTargets,
 }
@@ -1,12 +1,14 @@
-use eframe::egui::{self, Id, Image, Key, LayerId, TextureHandle, Ui, UiBuilder, Vec2, Widget};
+use eframe::egui::{self, Id, Key, LayerId, Rect, Ui, UiBuilder, emath::TSTransform};
+
+use crate::ui::tiled::TiledTexture;
 
 pub struct PinWindow {
-    texture_handle: TextureHandle,
+    texture: TiledTexture,
 }
 
 impl eframe::App for PinWindow {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut ui = Ui::new(
+        let ui = Ui::new(
             ctx.clone(),
             Id::new("pin"),
             UiBuilder::new()
@@ -14,10 +16,13 @@ impl eframe::App for PinWindow {
                 .max_rect(ctx.available_rect()),
         );
 
-        let screenshot_image = Image::from_texture(&self.texture_handle)
-            .fit_to_fraction(Vec2::splat(1f32))
-            .show_loading_spinner(false);
-        ui.centered_and_justified(|ui| screenshot_image.ui(ui));
+        // fit the screenshot into the window, centered
+        let available = ui.max_rect();
+        let size = self.texture.size();
+        let scale = f32::min(available.width() / size.x, available.height() / size.y);
+        let rect = Rect::from_center_size(available.center(), size * scale);
+        self.texture
+            .paint(ui.painter(), &TSTransform::new(rect.min.to_vec2(), scale));
         if ctx.input(|i| i.key_pressed(Key::Escape)) {
             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
         }
@@ -29,7 +34,7 @@ impl eframe::App for PinWindow {
 }
 
 impl PinWindow {
-    pub fn new(texture_handle: TextureHandle) -> Self {
-        Self { texture_handle }
+    pub fn new(texture: TiledTexture) -> Self {
+        Self { texture }
     }
 }